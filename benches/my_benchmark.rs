@@ -1,8 +1,10 @@
 use std::{collections::{BTreeMap, HashMap}, hash::BuildHasher, sync::{Mutex, RwLock}};
 
-use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
 use crossbeam::queue::ArrayQueue;
 use genefuse::aux::int_hasher::{CPPTrivialHasherBuilder, FxHasherBuilder};
+use genefuse::core::breakpoint_cluster::{cluster_breakpoints, BREAKPOINT_TOLERANCE};
+use genefuse::core::bench::MapReadBench;
 
 use rustc_hash::FxHashMap;
 
@@ -215,5 +217,114 @@ pub fn btree_hm(c: &mut Criterion) {
     // c.bench_function("rwlock", |b| b.iter(|| fibonacci(black_box(20))));
 }
 
+/// reads of the two contigs of testdata/tinyref.fa, then reads joining the end of contig1
+/// to the start of contig2 at several junctions
+fn testdata_bench() -> MapReadBench {
+    let mut bench = MapReadBench::new("testdata/tinyref.fa", "testdata/tinyref.csv").unwrap();
+
+    let contig1 = "GATCACAGGTCTATCACCCTATTAATTGGTATTTTCGTCTGGGGGGTGTGGAGCCGGAGCACCCTATGTCGCAGT";
+    let contig2 = "GTCTGCACAGCCGCTTTCCACACAGAACCCCCCCCTCCCCCCGCTTCTGGCAAACCCCAAAAACAAAGAACCCTA";
+    for contig in [contig1, contig2] {
+        for start in (0..=contig.len() - 60).step_by(5) {
+            bench.add_read(&format!("@read{}", bench.reads()), &contig[start..start + 60]);
+        }
+    }
+
+    for junction in (40..=70).step_by(5) {
+        let fusion = format!("{}{}", &contig1[contig1.len() - junction..], &contig2[..110 - junction]);
+        bench.add_read(&format!("@fusion{}", junction), &fusion);
+    }
+
+    bench
+}
+
+pub fn indexer_map_read(c: &mut Criterion) {
+    let bench = testdata_bench();
+    assert!(bench.agree());
+    assert!(bench.map_reads().iter().all(|segments| *segments > 0));
+    assert!(bench.map_reads().iter().rev().take(7).all(|segments| *segments == 2));
+
+    let mut group = c.benchmark_group("indexer_map_read");
+    group.throughput(Throughput::Elements(bench.reads() as u64));
+
+    group.bench_function("btree_map", |b| b.iter(|| black_box(bench.map_reads_legacy())));
+    group.bench_function("vote_table", |b| b.iter(|| black_box(bench.map_reads())));
+
+    group.finish();
+}
+
+// the clustering of FusionMapper::cluster_matches() before the hash lookup
+fn legacy_clusters(points: &[(i32, i32)]) -> Vec<usize> {
+    let mut clusters: Vec<Vec<(i32, i32)>> = Vec::new();
+
+    points
+        .iter()
+        .map(|&(left, right)| {
+            let found = clusters.iter().position(|members| {
+                members.iter().any(|&(l, r)| {
+                    (l - left).abs() <= BREAKPOINT_TOLERANCE && (r - right).abs() <= BREAKPOINT_TOLERANCE
+                })
+            });
+
+            match found {
+                Some(c) => {
+                    clusters[c].push((left, right));
+                    c
+                }
+                None => {
+                    clusters.push(vec![(left, right)]);
+                    clusters.len() - 1
+                }
+            }
+        })
+        .collect()
+}
+
+/// one gene pair of high-depth amplicon data, a hot breakpoint with a little jitter
+/// mixed with reads of many low-depth breakpoints
+fn amplicon_breakpoints(depth: usize) -> Vec<(i32, i32)> {
+    let mut seed = 42_u64;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as i32
+    };
+
+    (0..depth)
+        .map(|i| {
+            if i % 10 == 0 {
+                let background = next() % 2000;
+                (5000 + background * 10, -80000 - background * 10)
+            } else {
+                (18158 + next() % 3, -32008 - next() % 3)
+            }
+        })
+        .collect()
+}
+
+pub fn breakpoint_clustering(c: &mut Criterion) {
+    let mut group = c.benchmark_group("breakpoint_clustering");
+    group.sample_size(10);
+
+    for depth in [1_000, 10_000, 100_000] {
+        let points = amplicon_breakpoints(depth);
+        group.throughput(Throughput::Elements(depth as u64));
+
+        if depth <= 10_000 {
+            assert_eq!(legacy_clusters(&points), cluster_breakpoints(&points));
+            group.bench_with_input(BenchmarkId::new("greedy", depth), &points, |b, points| {
+                b.iter(|| black_box(legacy_clusters(points)))
+            });
+        }
+
+        group.bench_with_input(BenchmarkId::new("hash_lookup", depth), &points, |b, points| {
+            b.iter(|| black_box(cluster_breakpoints(points)))
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(benches, default_hash, trivial_hash, fx_hash, trivial_hash2, btree_hm);
-criterion_main!(benches);
+criterion_group!(mapping, indexer_map_read);
+criterion_group!(clustering, breakpoint_clustering);
+criterion_main!(benches, mapping, clustering);
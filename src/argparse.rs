@@ -1,7 +1,51 @@
-use clap::{arg, command, value_parser, ArgAction, ArgMatches};
+use clap::{arg, builder::RangedU64ValueParser, command, value_parser, ArgAction, ArgMatches, Command};
 
 pub(crate) fn parse_args() -> ArgMatches {
     let command = command!() // requires `cargo` feature
+        // -h is taken by --html
+        .disable_help_flag(true)
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("build-pon")
+                .about("aggregate the breakpoints of genefuse JSON reports of normal samples into a panel of normals for --pon")
+                .arg(
+                    arg!(
+                        -o --output <output> "file name to store the panel of normals, in TSV format"
+                    )
+                    .required(true)
+                    .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    arg!(
+                        --tolerance <tolerance> "fusions of different samples are the same junction if both breaks are at most this far apart, default is 5"
+                    )
+                    .required(false)
+                    .value_parser(value_parser!(i32).range(0..))
+                    .default_value("5")
+                )
+                .arg(
+                    arg!(
+                        --min_samples <min_samples> "only junctions found in at least this many samples are stored, default is 2"
+                    )
+                    .required(false)
+                    .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+                    .default_value("2")
+                )
+                .arg(
+                    arg!(
+                        <json> ... "genefuse JSON reports of normal samples"
+                    )
+                    .required(true)
+                    .value_parser(value_parser!(String)),
+                ),
+        )
+        .arg(
+            arg!(
+                --help "print help"
+            )
+            .action(ArgAction::Help)
+        )
         .arg(
             arg!(
                 -'1' --read1 <read1> "read1 file name"
@@ -73,7 +117,23 @@ pub(crate) fn parse_args() -> ArgMatches {
         )
         .arg(
             arg!(
-                -D --output_deletions <output_deletions> "long deletions are not output by default, enable this option to output them"
+                --duplication <duplication> "specify the least duplicated length of a intra-gene tandem duplication to report, default is 50"
+            )
+            .required(false)
+            .value_parser(value_parser!(usize))
+            .default_value("50")
+        )
+        .arg(
+            arg!(
+                --inversion <inversion> "specify the least distance between the breaks of a intra-gene inversion to report, default is 50"
+            )
+            .required(false)
+            .value_parser(value_parser!(usize))
+            .default_value("50")
+        )
+        .arg(
+            arg!(
+                -D --output_deletions <output_deletions> "long deletions and tandem duplications within a gene are not output by default, enable this option to output them"
             )
             .required(false)
             .action(ArgAction::SetTrue)
@@ -86,11 +146,308 @@ pub(crate) fn parse_args() -> ArgMatches {
             enable this option to output them")
             .required(false)
             .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --index_stats <index_stats> "file name to store k-mer uniqueness of every gene and exon in TSV format, not stored by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --paralog_unique_ratio <paralog_unique_ratio> "genes or exons with less unique k-mers than this ratio are flagged as undetectable because of paralogs, default is 0.5"
+            )
+            .required(false)
+            .value_parser(parse_ratio)
+            .default_value("0.5")
+        )
+        .arg(
+            arg!(
+                --exclude_bed <exclude_bed> "BED file of reference regions to ignore when removing reads that align contiguously to the genome, e.g. processed pseudogenes"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --exclude_contigs <exclude_contigs> "comma separated contig name patterns to ignore when removing reads that align contiguously to the genome, * and ? are supported, e.g. '*_alt,*_decoy,chrUn_*'"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .value_delimiter(',')
+        )
+        .arg(
+            arg!(
+                --rejected_reads <rejected_reads> "file name to store the candidate reads dropped by filters with the stage and reason, FASTQ if it ends with .fq or .fastq, TSV otherwise, not stored by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --consensus_fasta <consensus_fasta> "file name to store the junction consensus sequence of every fusion in FASTA format, for designing validation primers, not stored by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --fusion_fasta <fusion_fasta> "file name to store the spliced transcript and the translated protein of every fusion in FASTA format, not stored by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --vcf <vcf> "file name to store a pair of VCF 4.3 breakend records per fusion, not stored by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --vcf_pass_only <vcf_pass_only> "leave fusions marked in the FILTER column, such as panel of normals hits and read-through candidates, out of the VCF"
+            )
+            .required(false)
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --tsv <tsv> "file name to store one line per fusion in TSV format, with the splice motifs and the microhomology at the breaks, not stored by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --spanning_max_insert <spanning_max_insert> "a read pair with one mate in each gene supports a fusion only if its insert size over the junction is at most this, default is 1000"
+            )
+            .required(false)
+            .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+            .default_value("1000")
+        )
+        .arg(
+            arg!(
+                --count_spanning_pairs <count_spanning_pairs> "count unique spanning read pairs toward --unique, so that fusions supported mainly by spanning pairs are called, at least one split read is still needed to locate the break"
+            )
+            .required(false)
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --reciprocal_max_distance <reciprocal_max_distance> "two fusions are reciprocal, like both derivatives of a balanced translocation, if their breaks are at most this far from the expected ones in total, default is 20"
+            )
+            .required(false)
+            .value_parser(value_parser!(i32).range(0..))
+            .default_value("20")
+        )
+        .arg(
+            arg!(
+                --collapse_reciprocal <collapse_reciprocal> "report reciprocal fusions as one event with combined support, they are reported separately with a shared event ID by default"
+            )
+            .required(false)
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --min_score <min_score> "fusions with a confidence score lower than this are not reported, the score is 0~1, default is 0"
+            )
+            .required(false)
+            .value_parser(parse_ratio)
+            .default_value("0")
+        )
+        .arg(
+            arg!(
+                --sort_by <sort_by> "sort fusions by unique reads (then total reads) or by confidence score, default is unique"
+            )
+            .required(false)
+            .value_parser(["unique", "score"])
+            .default_value("unique")
+        )
+        .arg(
+            arg!(
+                --pon <pon> "panel of normals made by the build-pon subcommand, fusions at its junctions are flagged with the frequency in normal samples, not used by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --pon_tolerance <pon_tolerance> "a fusion matches a junction of the panel of normals if both breaks are at most this far apart, default is 5"
+            )
+            .required(false)
+            .value_parser(value_parser!(i32).range(0..))
+            .default_value("5")
+        )
+        .arg(
+            arg!(
+                --pon_remove <pon_remove> "remove fusions found in the panel of normals instead of flagging them"
+            )
+            .required(false)
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --circrna <circrna> "detect circular RNAs by their back-splice junctions at exon boundaries and compare them with the linear junctions, implies --rna"
+            )
+            .required(false)
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --rna <rna> "the reads are RNA-seq, breaks are moved to exon boundaries where the sequence allows it, calls are labelled canonical or non-canonical and scored lower away from exon boundaries"
+            )
+            .required(false)
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --hotspots <hotspots> "TSV of known junctions to genotype with every read crossing them, even below --unique or with short segments, columns are left_gene, left_position, right_gene, right_position and orientation (+/+, +/-, -/+ or -/-), not used by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --domains <domains> "TSV of protein domains to tell which ones fusion proteins keep, columns are gene or transcript, domain name, start and end in amino acids from 1, genes need the coding sequence in the gene list, not used by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --known_fusions <known_fusions> "TSV of known fusions to tag calls as known junction, known pair or novel, columns are 5' gene, 3' gene and optionally the last exon of the 5' gene, the first exon of the 3' gene and a source, not used by default"
+            )
+            .required(false)
+            .value_parser(value_parser!(String))
+            .default_value("")
+        )
+        .arg(
+            arg!(
+                --known_only <known_only> "only report fusions of gene pairs in --known_fusions"
+            )
+            .required(false)
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --readthrough_distance <readthrough_distance> "fusions of a gene with the next gene of the gene list downstream on the same strand, at most this far away, are flagged as read-through candidates, default is 100000"
+            )
+            .required(false)
+            .value_parser(value_parser!(i32).range(0..))
+            .default_value("100000")
+        )
+        .arg(
+            arg!(
+                --readthrough_demote <readthrough_demote> "report read-through candidates after all other fusions"
+            )
+            .required(false)
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --readthrough_remove <readthrough_remove> "remove read-through candidates instead of flagging them"
+            )
+            .required(false)
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(
+                --skip_key_dup_threshold <skip_key_dup_threshold> "k-mers found in more than this many gene positions are ignored when mapping reads, default is 5"
+            )
+            .required(false)
+            .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+            .default_value("5")
+        )
+        .arg(
+            arg!(
+                --major_key_requirement <major_key_requirement> "the least k-mer votes the major gene of a read needs, default is 40"
+            )
+            .required(false)
+            .value_parser(value_parser!(i32).range(1..))
+            .default_value("40")
+        )
+        .arg(
+            arg!(
+                --minor_key_requirement <minor_key_requirement> "the least k-mer votes the minor gene (and any further segment) of a read needs, default is 20"
+            )
+            .required(false)
+            .value_parser(value_parser!(i32).range(1..))
+            .default_value("20")
+        )
+        .arg(
+            arg!(
+                --mismatch_threshold <mismatch_threshold> "the most read bases not covered by the mapped genes, default is 10"
+            )
+            .required(false)
+            .value_parser(value_parser!(i32).range(0..))
+            .default_value("10")
+        )
+        .arg(
+            arg!(
+                --diff_threshold <diff_threshold> "reads whose edit distances to both sides of the fusion sum to this or more are removed, default is 5"
+            )
+            .required(false)
+            .value_parser(value_parser!(i32).range(1..))
+            .default_value("5")
+        )
+        .arg(
+            arg!(
+                --low_complexity_length <low_complexity_length> "a read part shorter than this at either side of the break is treated as low complexity and the read is removed, default is 20"
+            )
+            .required(false)
+            .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+            .default_value("20")
+        )
+        .arg(
+            arg!(
+                --low_complexity_discontinuity <low_complexity_discontinuity> "a read part with fewer base changes than this is treated as low complexity and the read is removed, default is 7"
+            )
+            .required(false)
+            .value_parser(value_parser!(i32).range(0..))
+            .default_value("7")
+        )
+        .arg(
+            arg!(
+                --min_ref_length <min_ref_length> "a fusion is reported only when the references at both sides of the break are longer than this, at least 9 so that both hold the 10 flank bases, default is 30"
+            )
+            .required(false)
+            .value_parser(RangedU64ValueParser::<usize>::new().range(9..))
+            .default_value("30")
+        )
+        .arg(
+            arg!(
+                --ref_flank_discontinuity <ref_flank_discontinuity> "a fusion is reported only when the 10 reference bases at each side of the break have more base changes than this, default is 2"
+            )
+            .required(false)
+            .value_parser(value_parser!(i32).range(0..=9))
+            .default_value("2")
         );
 
     command.get_matches()
 }
 
+fn parse_ratio(s: &str) -> Result<f64, String> {
+    let val = s.parse::<f64>().map_err(|e| e.to_string())?;
+
+    if !(0.0..=1.0).contains(&val) {
+        return Err(format!("{} is not in the range of 0 to 1", val));
+    }
+
+    Ok(val)
+}
+
 pub(crate) struct RunConfig {
     pub(crate) r1_file: String,
     pub(crate) r2_file: String,
@@ -101,8 +458,47 @@ pub(crate) struct RunConfig {
     pub(crate) thread_num: usize,
     pub(crate) unique: usize,
     pub(crate) deletion: usize,
+    pub(crate) duplication: usize,
+    pub(crate) inversion: usize,
     pub(crate) output_deletion: bool,
     pub(crate) output_untranslated: bool,
+    pub(crate) index_stats: String,
+    pub(crate) paralog_unique_ratio: f64,
+    pub(crate) exclude_bed: String,
+    pub(crate) exclude_contigs: Vec<String>,
+    pub(crate) rejected_reads: String,
+    pub(crate) consensus_fasta: String,
+    pub(crate) tsv: String,
+    pub(crate) fusion_fasta: String,
+    pub(crate) vcf: String,
+    pub(crate) vcf_pass_only: bool,
+    pub(crate) spanning_max_insert: usize,
+    pub(crate) count_spanning_pairs: bool,
+    pub(crate) reciprocal_max_distance: i32,
+    pub(crate) collapse_reciprocal: bool,
+    pub(crate) min_score: f64,
+    pub(crate) sort_by: String,
+    pub(crate) pon: String,
+    pub(crate) pon_tolerance: i32,
+    pub(crate) pon_remove: bool,
+    pub(crate) hotspots: String,
+    pub(crate) domains: String,
+    pub(crate) known_fusions: String,
+    pub(crate) known_only: bool,
+    pub(crate) readthrough_distance: i32,
+    pub(crate) readthrough_demote: bool,
+    pub(crate) readthrough_remove: bool,
+    pub(crate) rna: bool,
+    pub(crate) circrna: bool,
+    pub(crate) skip_key_dup_threshold: usize,
+    pub(crate) major_key_requirement: i32,
+    pub(crate) minor_key_requirement: i32,
+    pub(crate) mismatch_threshold: i32,
+    pub(crate) diff_threshold: i32,
+    pub(crate) low_complexity_length: usize,
+    pub(crate) low_complexity_discontinuity: i32,
+    pub(crate) min_ref_length: usize,
+    pub(crate) ref_flank_discontinuity: i32,
 }
 
 impl RunConfig {
@@ -117,14 +513,83 @@ impl RunConfig {
             thread_num: args.remove_one::<usize>("thread").unwrap(),
             unique: args.remove_one::<usize>("unique").unwrap(),
             deletion: args.remove_one::<usize>("deletion").unwrap(),
+            duplication: args.remove_one::<usize>("duplication").unwrap(),
+            inversion: args.remove_one::<usize>("inversion").unwrap(),
             output_deletion: args.remove_one::<bool>("output_deletions").unwrap(),
             output_untranslated: args.remove_one::<bool>("output_untranslated_fusions").unwrap(),
+            index_stats: args.remove_one::<String>("index_stats").unwrap(),
+            paralog_unique_ratio: args.remove_one::<f64>("paralog_unique_ratio").unwrap(),
+            exclude_bed: args.remove_one::<String>("exclude_bed").unwrap(),
+            exclude_contigs: args
+                .remove_many::<String>("exclude_contigs")
+                .map(|v| v.filter(|p| !p.is_empty()).collect())
+                .unwrap_or_default(),
+            rejected_reads: args.remove_one::<String>("rejected_reads").unwrap(),
+            consensus_fasta: args.remove_one::<String>("consensus_fasta").unwrap(),
+            tsv: args.remove_one::<String>("tsv").unwrap(),
+            fusion_fasta: args.remove_one::<String>("fusion_fasta").unwrap(),
+            vcf: args.remove_one::<String>("vcf").unwrap(),
+            vcf_pass_only: args.remove_one::<bool>("vcf_pass_only").unwrap(),
+            spanning_max_insert: args.remove_one::<usize>("spanning_max_insert").unwrap(),
+            count_spanning_pairs: args.remove_one::<bool>("count_spanning_pairs").unwrap(),
+            reciprocal_max_distance: args.remove_one::<i32>("reciprocal_max_distance").unwrap(),
+            collapse_reciprocal: args.remove_one::<bool>("collapse_reciprocal").unwrap(),
+            min_score: args.remove_one::<f64>("min_score").unwrap(),
+            sort_by: args.remove_one::<String>("sort_by").unwrap(),
+            pon: args.remove_one::<String>("pon").unwrap(),
+            pon_tolerance: args.remove_one::<i32>("pon_tolerance").unwrap(),
+            pon_remove: args.remove_one::<bool>("pon_remove").unwrap(),
+            hotspots: args.remove_one::<String>("hotspots").unwrap(),
+            domains: args.remove_one::<String>("domains").unwrap(),
+            known_fusions: args.remove_one::<String>("known_fusions").unwrap(),
+            known_only: args.remove_one::<bool>("known_only").unwrap(),
+            readthrough_distance: args.remove_one::<i32>("readthrough_distance").unwrap(),
+            readthrough_demote: args.remove_one::<bool>("readthrough_demote").unwrap(),
+            readthrough_remove: args.remove_one::<bool>("readthrough_remove").unwrap(),
+            rna: args.remove_one::<bool>("rna").unwrap(),
+            circrna: args.remove_one::<bool>("circrna").unwrap(),
+            skip_key_dup_threshold: args.remove_one::<usize>("skip_key_dup_threshold").unwrap(),
+            major_key_requirement: args.remove_one::<i32>("major_key_requirement").unwrap(),
+            minor_key_requirement: args.remove_one::<i32>("minor_key_requirement").unwrap(),
+            mismatch_threshold: args.remove_one::<i32>("mismatch_threshold").unwrap(),
+            diff_threshold: args.remove_one::<i32>("diff_threshold").unwrap(),
+            low_complexity_length: args.remove_one::<usize>("low_complexity_length").unwrap(),
+            low_complexity_discontinuity: args.remove_one::<i32>("low_complexity_discontinuity").unwrap(),
+            min_ref_length: args.remove_one::<usize>("min_ref_length").unwrap(),
+            ref_flank_discontinuity: args.remove_one::<i32>("ref_flank_discontinuity").unwrap(),
         }
     
     }
 }
 
-pub(crate) fn set_configs() -> RunConfig {
+pub(crate) struct BuildPonConfig {
+    pub(crate) json_files: Vec<String>,
+    pub(crate) output: String,
+    pub(crate) tolerance: i32,
+    pub(crate) min_samples: usize,
+}
 
-    RunConfig::from_args(parse_args())
+impl BuildPonConfig {
+    fn from_args(mut args: ArgMatches) -> BuildPonConfig {
+        Self {
+            json_files: args.remove_many::<String>("json").unwrap().collect(),
+            output: args.remove_one::<String>("output").unwrap(),
+            tolerance: args.remove_one::<i32>("tolerance").unwrap(),
+            min_samples: args.remove_one::<usize>("min_samples").unwrap(),
+        }
+    }
+}
+
+pub(crate) enum Config {
+    Run(Box<RunConfig>),
+    BuildPon(BuildPonConfig),
+}
+
+pub(crate) fn set_configs() -> Config {
+    let mut args = parse_args();
+
+    match args.remove_subcommand() {
+        Some((_, sub_args)) => Config::BuildPon(BuildPonConfig::from_args(sub_args)),
+        None => Config::Run(Box::new(RunConfig::from_args(args))),
+    }
 }
@@ -1,15 +1,50 @@
 use std::sync::{OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 pub(crate) struct GlobalSettings {
+    // fusions with FILTER marks are left out of the VCF
     pub(crate) marked_only_for_vcf: bool,
     pub(crate) unique_requirement: usize,
     pub(crate) deletion_threshold: usize,
+    pub(crate) duplication_threshold: usize,
+    pub(crate) inversion_threshold: usize,
     pub(crate) output_deletions: bool,
     pub(crate) output_untranslated: bool,
     pub(crate) skip_key_dup_threshold: usize,
     pub(crate) major_gene_key_requirement: i32,
     pub(crate) minor_gene_key_requirement: i32,
     pub(crate) mismatch_threshold: i32,
+    pub(crate) paralog_unique_ratio: f64,
+    pub(crate) index_stats_file: String,
+    pub(crate) exclude_bed_file: String,
+    pub(crate) exclude_contigs: Vec<String>,
+    pub(crate) rejected_reads_file: String,
+    pub(crate) consensus_fasta_file: String,
+    pub(crate) tsv_file: String,
+    pub(crate) fusion_fasta_file: String,
+    pub(crate) vcf_file: String,
+    pub(crate) spanning_max_insert: usize,
+    pub(crate) count_spanning_pairs: bool,
+    pub(crate) reciprocal_max_distance: i32,
+    pub(crate) collapse_reciprocal: bool,
+    pub(crate) min_score: f64,
+    pub(crate) sort_by_score: bool,
+    pub(crate) pon_file: String,
+    pub(crate) pon_tolerance: i32,
+    pub(crate) pon_remove: bool,
+    pub(crate) hotspot_file: String,
+    pub(crate) domain_file: String,
+    pub(crate) known_fusions_file: String,
+    pub(crate) known_only: bool,
+    pub(crate) readthrough_distance: i32,
+    pub(crate) readthrough_demote: bool,
+    pub(crate) readthrough_remove: bool,
+    pub(crate) rna_mode: bool,
+    pub(crate) circrna_mode: bool,
+    pub(crate) diff_threshold: i32,
+    pub(crate) low_complexity_length: usize,
+    pub(crate) low_complexity_discontinuity: i32,
+    pub(crate) min_ref_length: usize,
+    pub(crate) ref_flank_discontinuity: i32,
 }
 
 impl Default for GlobalSettings {
@@ -18,12 +53,46 @@ impl Default for GlobalSettings {
             marked_only_for_vcf: false,
             unique_requirement: 2,
             deletion_threshold: 50,
+            duplication_threshold: 50,
+            inversion_threshold: 50,
             output_deletions: false,
             output_untranslated: false,
             skip_key_dup_threshold: 5,
             major_gene_key_requirement: 40,
             minor_gene_key_requirement: 20,
             mismatch_threshold: 10,
+            paralog_unique_ratio: 0.5,
+            index_stats_file: String::new(),
+            exclude_bed_file: String::new(),
+            exclude_contigs: Vec::new(),
+            rejected_reads_file: String::new(),
+            consensus_fasta_file: String::new(),
+            tsv_file: String::new(),
+            fusion_fasta_file: String::new(),
+            vcf_file: String::new(),
+            spanning_max_insert: 1000,
+            count_spanning_pairs: false,
+            reciprocal_max_distance: 20,
+            collapse_reciprocal: false,
+            min_score: 0.0,
+            sort_by_score: false,
+            pon_file: String::new(),
+            pon_tolerance: 5,
+            pon_remove: false,
+            hotspot_file: String::new(),
+            domain_file: String::new(),
+            known_fusions_file: String::new(),
+            known_only: false,
+            readthrough_distance: 100000,
+            readthrough_demote: false,
+            readthrough_remove: false,
+            rna_mode: false,
+            circrna_mode: false,
+            diff_threshold: 5,
+            low_complexity_length: 20,
+            low_complexity_discontinuity: 7,
+            min_ref_length: 30,
+            ref_flank_discontinuity: 2,
         }
     }
 }
@@ -44,6 +113,16 @@ impl GlobalSettings {
         self.deletion_threshold = val;
     }
 
+    #[inline]
+    pub(crate) fn set_duplication_threshold(&mut self, val: usize) {
+        self.duplication_threshold = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_inversion_threshold(&mut self, val: usize) {
+        self.inversion_threshold = val;
+    }
+
     #[inline]
     pub(crate) fn set_output_deletions(&mut self, flag: bool) {
         self.output_deletions = flag;
@@ -53,6 +132,219 @@ impl GlobalSettings {
     pub(crate) fn set_output_untranslated(&mut self, flag: bool) {
         self.output_untranslated = flag;
     }
+
+    #[inline]
+    pub(crate) fn set_paralog_unique_ratio(&mut self, val: f64) {
+        self.paralog_unique_ratio = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_index_stats_file(&mut self, val: String) {
+        self.index_stats_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_exclude_bed_file(&mut self, val: String) {
+        self.exclude_bed_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_exclude_contigs(&mut self, val: Vec<String>) {
+        self.exclude_contigs = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_rejected_reads_file(&mut self, val: String) {
+        self.rejected_reads_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_consensus_fasta_file(&mut self, val: String) {
+        self.consensus_fasta_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_tsv_file(&mut self, val: String) {
+        self.tsv_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_fusion_fasta_file(&mut self, val: String) {
+        self.fusion_fasta_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_vcf_file(&mut self, val: String) {
+        self.vcf_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_spanning_max_insert(&mut self, val: usize) {
+        self.spanning_max_insert = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_count_spanning_pairs(&mut self, flag: bool) {
+        self.count_spanning_pairs = flag;
+    }
+
+    #[inline]
+    pub(crate) fn set_reciprocal_max_distance(&mut self, val: i32) {
+        self.reciprocal_max_distance = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_collapse_reciprocal(&mut self, flag: bool) {
+        self.collapse_reciprocal = flag;
+    }
+
+    #[inline]
+    pub(crate) fn set_pon_file(&mut self, val: String) {
+        self.pon_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_pon_tolerance(&mut self, val: i32) {
+        self.pon_tolerance = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_pon_remove(&mut self, flag: bool) {
+        self.pon_remove = flag;
+    }
+
+    #[inline]
+    pub(crate) fn set_hotspot_file(&mut self, val: String) {
+        self.hotspot_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_domain_file(&mut self, val: String) {
+        self.domain_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_known_fusions_file(&mut self, val: String) {
+        self.known_fusions_file = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_known_only(&mut self, flag: bool) {
+        self.known_only = flag;
+    }
+
+    #[inline]
+    pub(crate) fn set_readthrough_distance(&mut self, val: i32) {
+        self.readthrough_distance = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_readthrough_demote(&mut self, flag: bool) {
+        self.readthrough_demote = flag;
+    }
+
+    #[inline]
+    pub(crate) fn set_readthrough_remove(&mut self, flag: bool) {
+        self.readthrough_remove = flag;
+    }
+
+    #[inline]
+    pub(crate) fn set_rna_mode(&mut self, flag: bool) {
+        self.rna_mode = flag;
+    }
+
+    #[inline]
+    pub(crate) fn set_circrna_mode(&mut self, flag: bool) {
+        self.circrna_mode = flag;
+    }
+
+    #[inline]
+    pub(crate) fn set_min_score(&mut self, val: f64) {
+        self.min_score = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_sort_by_score(&mut self, flag: bool) {
+        self.sort_by_score = flag;
+    }
+
+    #[inline]
+    pub(crate) fn set_skip_key_dup_threshold(&mut self, val: usize) {
+        self.skip_key_dup_threshold = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_major_gene_key_requirement(&mut self, val: i32) {
+        self.major_gene_key_requirement = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_minor_gene_key_requirement(&mut self, val: i32) {
+        self.minor_gene_key_requirement = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_mismatch_threshold(&mut self, val: i32) {
+        self.mismatch_threshold = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_diff_threshold(&mut self, val: i32) {
+        self.diff_threshold = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_low_complexity_length(&mut self, val: usize) {
+        self.low_complexity_length = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_low_complexity_discontinuity(&mut self, val: i32) {
+        self.low_complexity_discontinuity = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_min_ref_length(&mut self, val: usize) {
+        self.min_ref_length = val;
+    }
+
+    #[inline]
+    pub(crate) fn set_ref_flank_discontinuity(&mut self, val: i32) {
+        self.ref_flank_discontinuity = val;
+    }
+
+    /// filter thresholds of this run, echoed in the reports
+    pub(crate) fn thresholds(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("unique", self.unique_requirement.to_string()),
+            ("deletion", self.deletion_threshold.to_string()),
+            ("duplication", self.duplication_threshold.to_string()),
+            ("inversion", self.inversion_threshold.to_string()),
+            ("spanning_max_insert", self.spanning_max_insert.to_string()),
+            ("count_spanning_pairs", self.count_spanning_pairs.to_string()),
+            ("reciprocal_max_distance", self.reciprocal_max_distance.to_string()),
+            ("collapse_reciprocal", self.collapse_reciprocal.to_string()),
+            ("min_score", self.min_score.to_string()),
+            ("sort_by_score", self.sort_by_score.to_string()),
+            ("pon_tolerance", self.pon_tolerance.to_string()),
+            ("pon_remove", self.pon_remove.to_string()),
+            ("known_only", self.known_only.to_string()),
+            ("readthrough_distance", self.readthrough_distance.to_string()),
+            ("readthrough_demote", self.readthrough_demote.to_string()),
+            ("readthrough_remove", self.readthrough_remove.to_string()),
+            ("rna", self.rna_mode.to_string()),
+            ("circrna", self.circrna_mode.to_string()),
+            ("skip_key_dup_threshold", self.skip_key_dup_threshold.to_string()),
+            ("major_key_requirement", self.major_gene_key_requirement.to_string()),
+            ("minor_key_requirement", self.minor_gene_key_requirement.to_string()),
+            ("mismatch_threshold", self.mismatch_threshold.to_string()),
+            ("diff_threshold", self.diff_threshold.to_string()),
+            ("low_complexity_length", self.low_complexity_length.to_string()),
+            ("low_complexity_discontinuity", self.low_complexity_discontinuity.to_string()),
+            ("min_ref_length", self.min_ref_length.to_string()),
+            ("ref_flank_discontinuity", self.ref_flank_discontinuity.to_string()),
+        ]
+    }
 }
 
 static GLOBAL_SETTINGS: OnceLock<RwLock<GlobalSettings>> = OnceLock::new();
@@ -0,0 +1,123 @@
+use super::common::GenePos;
+
+// bases a reference read needs at each side of a break to be counted
+const WILD_TYPE_ANCHOR: i32 = 10;
+
+// a read lying in one gene with more mismatches may still cross a junction with a short segment
+pub(crate) const WILD_TYPE_MAX_MISMATCHES: usize = 5;
+
+// 95% confidence
+const Z: f64 = 1.96;
+
+/// Fraction of the reads at the breaks that carry the fusion.
+///
+/// Fusion reads are the split reads of the result, reference reads lie contiguously in a
+/// gene across its break. The break of each side is covered by its own reference reads, their
+/// mean is the reference depth of the locus.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct AlleleFraction {
+    pub(crate) m_alt_reads: usize,
+    pub(crate) m_left_ref_reads: usize,
+    pub(crate) m_right_ref_reads: usize,
+    pub(crate) m_vaf: f64,
+    // Wilson score interval
+    pub(crate) m_ci_low: f64,
+    pub(crate) m_ci_high: f64,
+}
+
+impl AlleleFraction {
+    pub(crate) fn new(alt_reads: usize, left_ref_reads: usize, right_ref_reads: usize) -> Self {
+        let alt = alt_reads as f64;
+        let n = alt + (left_ref_reads + right_ref_reads) as f64 / 2.0;
+        let (m_ci_low, m_ci_high) = wilson_interval(alt, n);
+
+        Self {
+            m_alt_reads: alt_reads,
+            m_left_ref_reads: left_ref_reads,
+            m_right_ref_reads: right_ref_reads,
+            m_vaf: if n > 0.0 { alt / n } else { 0.0 },
+            m_ci_low,
+            m_ci_high,
+        }
+    }
+}
+
+/// `(low, high)` of the fraction of `n` observations that `k` are, `(0, 1)` without any
+pub(crate) fn wilson_interval(k: f64, n: f64) -> (f64, f64) {
+    if n <= 0.0 {
+        return (0.0, 1.0);
+    }
+
+    let p = k / n;
+    let z2 = Z * Z;
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let half = Z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+
+    ((center - half).max(0.0), (center + half).min(1.0))
+}
+
+/// first and last forward gene positions covered by a read of `len` bases starting at `gp`
+pub(crate) fn forward_interval(gp: &GenePos, len: i32) -> (i32, i32) {
+    // the reverse strand counts down from -(gene length - 1), base x being at -x
+    let start = if gp.position >= 0 {
+        gp.position
+    } else {
+        -gp.position - (len - 1)
+    };
+
+    (start, start + len - 1)
+}
+
+/// The break of one side of a fusion is between forward bases `gap` and `gap + 1` of its gene.
+///
+/// Positions increase along the fusion, so the left gene ends at its break and the right gene
+/// starts at it, the other way round in forward positions when the side is reversed.
+pub(crate) fn break_gap(gp: &GenePos, is_left: bool) -> i32 {
+    match (is_left, gp.position >= 0) {
+        (true, true) => gp.position,
+        (true, false) => -gp.position - 1,
+        (false, true) => gp.position - 1,
+        (false, false) => -gp.position,
+    }
+}
+
+/// reads of `intervals`, sorted by start, with `WILD_TYPE_ANCHOR` bases at both sides of `gap`
+pub(crate) fn count_spanning(intervals: &[(i32, i32)], gap: i32) -> usize {
+    let candidates = intervals.partition_point(|&(start, _)| start <= gap - (WILD_TYPE_ANCHOR - 1));
+
+    intervals[..candidates]
+        .iter()
+        .filter(|&&(_, end)| end >= gap + WILD_TYPE_ANCHOR)
+        .count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gp(position: i32) -> GenePos {
+        GenePos { contig: 0, position }
+    }
+
+    #[test]
+    fn reference_reads_and_vaf() {
+        // reads of 50 bases, the break after forward base 100 needing bases 91 to 110
+        let mut intervals = vec![(51, 100), (61, 110), (70, 119), (91, 140), (92, 141), (101, 150)];
+        intervals.sort_unstable();
+        assert_eq!(count_spanning(&intervals, 100), 3);
+
+        // the same read on the reverse strand, and the gaps of both strands
+        assert_eq!(forward_interval(&gp(-109), 50), (60, 109));
+        assert_eq!(break_gap(&gp(100), true), break_gap(&gp(-101), true));
+        assert_eq!(break_gap(&gp(101), false), break_gap(&gp(-100), false));
+
+        let af = AlleleFraction::new(10, 8, 12);
+        assert!((af.m_vaf - 0.5).abs() < 1e-9);
+        assert!((af.m_ci_low - 0.299).abs() < 1e-3);
+        assert!((af.m_ci_high - 0.701).abs() < 1e-3);
+
+        assert_eq!(wilson_interval(0.0, 0.0), (0.0, 1.0));
+        let (low, high) = wilson_interval(5.0, 5.0);
+        assert!(low > 0.5 && high == 1.0);
+    }
+}
@@ -0,0 +1,59 @@
+//! Entry points of benches/my_benchmark.rs into the crate private mapping code.
+
+use super::{fusion::Fusion, fusion_scan::{Error, MULTI_CSV_MODE}, indexer::Indexer, read::SequenceRead};
+
+/// an index of a reference for the genes of a fusion csv and the reads to map on it
+pub struct MapReadBench {
+    m_indexer: Indexer,
+    m_reads: Vec<SequenceRead>,
+}
+
+impl MapReadBench {
+    pub fn new(ref_file: &str, fusion_file: &str) -> Result<Self, Error> {
+        // no multi csv scan around the bench, show the progress bar of the indexing
+        MULTI_CSV_MODE.get_or_init(|| false);
+
+        let fusions = Fusion::parse_csv(fusion_file)?;
+        let mut m_indexer = Indexer::new(ref_file, fusions)?;
+        m_indexer.make_index();
+
+        Ok(Self {
+            m_indexer,
+            m_reads: Vec::new(),
+        })
+    }
+
+    pub fn add_read(&mut self, name: &str, seq: &str) {
+        self.m_reads.push(SequenceRead::new(
+            name.to_string(),
+            seq.to_string(),
+            "+".to_string(),
+            "E".repeat(seq.len()),
+            true,
+        ));
+    }
+
+    pub fn reads(&self) -> usize {
+        self.m_reads.len()
+    }
+
+    /// segments of each read by `Indexer::map_read()`
+    pub fn map_reads(&self) -> Vec<usize> {
+        self.m_reads.iter().map(|r| self.m_indexer.map_read(r).len()).collect()
+    }
+
+    /// segments of each read by the voting `Indexer::map_read()` replaced
+    pub fn map_reads_legacy(&self) -> Vec<usize> {
+        self.m_reads.iter().map(|r| self.m_indexer.map_read_legacy(r).len()).collect()
+    }
+
+    /// whether both votings cut every read into the same segments
+    pub fn agree(&self) -> bool {
+        self.m_reads.iter().all(|r| {
+            let segments = self.m_indexer.map_read(r);
+            let legacy = self.m_indexer.map_read_legacy(r);
+            segments.len() == legacy.len()
+                && segments.iter().zip(legacy.iter()).all(|(a, b)| a.to_string() == b.to_string())
+        })
+    }
+}
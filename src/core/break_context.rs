@@ -0,0 +1,105 @@
+/// Reference sequence around the breaks of a fusion, for telling how it was made.
+///
+/// Splice junctions keep the intron dinucleotides of the donor after the left break and of the
+/// acceptor before the right break. Microhomology is sequence both genes have at the junction,
+/// which the breaks can be moved along without changing the fusion sequence.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct BreakContext {
+    // the 2 bases of the left gene after its break and of the right gene before its break
+    pub(crate) m_donor: String,
+    pub(crate) m_acceptor: String,
+    pub(crate) m_microhomology: String,
+    // bases the breaks can be moved back and forth along the microhomology
+    pub(crate) m_homology_before: i32,
+    pub(crate) m_homology_after: i32,
+}
+
+impl BreakContext {
+    /// From the references of a fusion, along its strand: `left_ref` ends at the left break and
+    /// `left_ref_ext` goes on from it in the left gene, `right_ref` starts at the right break and
+    /// `right_ref_ext` leads to it in the right gene. The break bases are in both of each pair.
+    pub(crate) fn new(left_ref: &str, left_ref_ext: &str, right_ref: &str, right_ref_ext: &str) -> Self {
+        let left_ref = left_ref.as_bytes();
+        let left_after = left_ref_ext.as_bytes().get(1..).unwrap_or_default();
+        let right_ref = right_ref.as_bytes();
+        let right_before = right_ref_ext
+            .as_bytes()
+            .get(..right_ref_ext.len().saturating_sub(1))
+            .unwrap_or_default();
+
+        let after = left_after
+            .iter()
+            .zip(right_ref.iter())
+            .take_while(|(a, b)| a == b && **a != b'N')
+            .count();
+        let before = left_ref
+            .iter()
+            .rev()
+            .zip(right_before.iter().rev())
+            .take_while(|(a, b)| a == b && **a != b'N')
+            .count();
+
+        let mut microhomology = String::from_utf8_lossy(&left_ref[left_ref.len() - before..]).to_string();
+        microhomology.push_str(&String::from_utf8_lossy(&right_ref[..after]));
+
+        Self {
+            m_donor: String::from_utf8_lossy(left_after.get(..2).unwrap_or_default()).to_string(),
+            m_acceptor: String::from_utf8_lossy(right_before.get(right_before.len().saturating_sub(2)..).unwrap_or_default())
+                .to_string(),
+            m_microhomology: microhomology,
+            m_homology_before: before as i32,
+            m_homology_after: after as i32,
+        }
+    }
+
+    /// `GT-AG` for a donor GT and an acceptor AG
+    pub(crate) fn splice_motif(&self) -> String {
+        format!("{}-{}", self.m_donor, self.m_acceptor)
+    }
+
+    /// canonical for GT-AG, semi-canonical for the minor GC-AG and AT-AC introns
+    pub(crate) fn splice_class(&self) -> &'static str {
+        match (self.m_donor.as_str(), self.m_acceptor.as_str()) {
+            ("GT", "AG") => "canonical",
+            ("GC", "AG") | ("AT", "AC") => "semi-canonical",
+            _ => "non-canonical",
+        }
+    }
+
+    pub(crate) fn microhomology_len(&self) -> i32 {
+        self.m_homology_before + self.m_homology_after
+    }
+
+    /// first and last gene positions the break at `pos` can be at, along its strand
+    pub(crate) fn break_range(&self, pos: i32) -> (i32, i32) {
+        (pos - self.m_homology_before, pos + self.m_homology_after)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn motif_and_microhomology() {
+        // left exon ...CCTT|gtaag, right intron ...tttcag|CATT...
+        let bc = BreakContext::new("TTCCTT", "TGTAAG", "CATTCC", "TTTCAGC");
+        assert_eq!(bc.splice_motif(), "GT-AG");
+        assert_eq!(bc.splice_class(), "canonical");
+        assert_eq!(bc.microhomology_len(), 0);
+        assert_eq!(bc.m_microhomology, "");
+
+        // the left gene goes on with AC and had TG, the right gene has TG before and AC at its break
+        let bc = BreakContext::new("AAATG", "GACTTT", "ACGGG", "CCTGA");
+        assert_eq!(bc.m_homology_before, 2);
+        assert_eq!(bc.m_homology_after, 2);
+        assert_eq!(bc.m_microhomology, "TGAC");
+        assert_eq!(bc.break_range(100), (98, 102));
+        assert_eq!(bc.break_range(-200), (-202, -198));
+        assert_eq!(bc.splice_class(), "non-canonical");
+
+        // short references don't panic
+        let bc = BreakContext::new("", "A", "", "");
+        assert_eq!(bc, BreakContext::default());
+    }
+}
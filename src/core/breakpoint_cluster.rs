@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use crate::aux::int_hasher::FxHasherBuilder;
+
+/// Two reads support the same fusion when both of their break positions differ by at most this.
+pub const BREAKPOINT_TOLERANCE: i32 = 3;
+
+/// Cluster `(left, right)` break positions of one gene pair, returning the cluster of every point.
+///
+/// The result is the same as putting each point, in order, into the first cluster that has a
+/// member within `BREAKPOINT_TOLERANCE` of it, or into a new cluster if there is none. Clusters
+/// are numbered in the order they are created.
+///
+/// Instead of comparing a point with every member of every cluster, the lowest cluster holding
+/// each distinct position is kept in a hash map, and only the positions within the tolerance
+/// are looked up. That's linear in the number of points however deep one breakpoint is covered.
+pub fn cluster_breakpoints(points: &[(i32, i32)]) -> Vec<usize> {
+    let mut lowest_cluster: HashMap<(i32, i32), usize, FxHasherBuilder> =
+        HashMap::with_capacity_and_hasher(points.len(), FxHasherBuilder::default());
+    let mut clusters = 0;
+
+    points
+        .iter()
+        .map(|&(left, right)| {
+            let mut found: Option<usize> = None;
+
+            for l in (left - BREAKPOINT_TOLERANCE)..=(left + BREAKPOINT_TOLERANCE) {
+                for r in (right - BREAKPOINT_TOLERANCE)..=(right + BREAKPOINT_TOLERANCE) {
+                    if let Some(&c) = lowest_cluster.get(&(l, r)) {
+                        found = Some(found.map_or(c, |f| f.min(c)));
+                    }
+                }
+            }
+
+            let cluster = found.unwrap_or_else(|| {
+                clusters += 1;
+                clusters - 1
+            });
+
+            // a point already at this position is within the tolerance, so `cluster` can't be higher
+            lowest_cluster.insert((left, right), cluster);
+
+            cluster
+        })
+        .collect()
+}
+
+/// Cluster reads of one chain of contigs by the `(left, right)` break positions of each of their
+/// junctions, returning the cluster of every read. Clusters are numbered in the order they are
+/// created.
+///
+/// Reads are clustered by their first junction with `cluster_breakpoints()`, then every cluster
+/// is split the same way by the next junction, so reads of a cluster have all their breaks close.
+pub fn cluster_chain_breakpoints(chains: &[Vec<(i32, i32)>]) -> Vec<usize> {
+    let junctions = chains.first().map_or(0, |c| c.len());
+    let mut clusters = vec![0; chains.len()];
+
+    for j in 0..junctions {
+        let mut members: Vec<Vec<usize>> = Vec::new();
+        for (i, &c) in clusters.iter().enumerate() {
+            if c == members.len() {
+                members.push(Vec::new());
+            }
+            members[c].push(i);
+        }
+
+        let mut split = vec![(0, 0); chains.len()];
+        for (c, reads) in members.iter().enumerate() {
+            let points = reads.iter().map(|&i| chains[i][j]).collect::<Vec<_>>();
+            for (&i, sub) in reads.iter().zip(cluster_breakpoints(&points)) {
+                split[i] = (c, sub);
+            }
+        }
+
+        let mut renumbered: HashMap<(usize, usize), usize, FxHasherBuilder> =
+            HashMap::with_hasher(FxHasherBuilder::default());
+        for (i, key) in split.into_iter().enumerate() {
+            let next = renumbered.len();
+            clusters[i] = *renumbered.entry(key).or_insert(next);
+        }
+    }
+
+    clusters
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // the clustering FusionMapper::cluster_matches() did before, comparing with every member
+    fn greedy_clusters(points: &[(i32, i32)]) -> Vec<usize> {
+        let mut clusters: Vec<Vec<(i32, i32)>> = Vec::new();
+
+        points
+            .iter()
+            .map(|&(left, right)| {
+                let found = clusters.iter().position(|members| {
+                    members.iter().any(|&(l, r)| {
+                        (l - left).abs() <= BREAKPOINT_TOLERANCE
+                            && (r - right).abs() <= BREAKPOINT_TOLERANCE
+                    })
+                });
+
+                match found {
+                    Some(c) => {
+                        clusters[c].push((left, right));
+                        c
+                    }
+                    None => {
+                        clusters.push(vec![(left, right)]);
+                        clusters.len() - 1
+                    }
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_as_greedy() {
+        // chains of points a bit more than the tolerance apart make clusters grow towards each other
+        let mut points = vec![(100, -50), (104, -50), (108, -50), (106, -47), (103, -50), (100, -54)];
+
+        let mut seed = 12345_u64;
+        for _ in 0..3000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let left = 1000 + ((seed >> 33) % 60) as i32;
+            let right = -2000 - ((seed >> 45) % 40) as i32;
+            points.push((left, right));
+        }
+
+        assert_eq!(cluster_breakpoints(&points), greedy_clusters(&points));
+        assert_eq!(cluster_breakpoints(&[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn chains_split_by_every_junction() {
+        let chains = vec![
+            vec![(100, -50), (300, 900)],
+            // same first junction, second one far away
+            vec![(101, -50), (400, 900)],
+            vec![(102, -51), (302, 901)],
+            vec![(500, -50), (300, 900)],
+            vec![(100, -49), (401, 899)],
+        ];
+
+        assert_eq!(cluster_chain_breakpoints(&chains), vec![0, 1, 0, 2, 1]);
+        assert_eq!(cluster_chain_breakpoints(&[]), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,132 @@
+use super::fusion_result::FusionResult;
+
+/// A circular RNA, found by its back-splice junction joining the end of its last exon to the
+/// start of its first one.
+///
+/// The splice sites of the junction are also used by the linear transcript, the donor of the
+/// last exon joined to the next exon and the acceptor of the first exon to the previous one.
+/// Their mean is the linear expression the circle is compared with.
+#[derive(Debug, Clone)]
+pub(crate) struct CircRna {
+    pub(crate) m_contig: i16,
+    pub(crate) m_gene: String,
+    pub(crate) m_chr: String,
+    pub(crate) m_first_exon: i32,
+    pub(crate) m_last_exon: i32,
+    // chromosome positions of the acceptor of the first exon and the donor of the last one
+    pub(crate) m_acceptor_pos: i32,
+    pub(crate) m_donor_pos: i32,
+    pub(crate) m_unique: i32,
+    pub(crate) m_reads: usize,
+    pub(crate) m_acceptor_linear_reads: usize,
+    pub(crate) m_donor_linear_reads: usize,
+}
+
+impl CircRna {
+    /// `first` and `last` as given by `exon_junction`
+    pub(crate) fn new(fr: &FusionResult, first: (i32, i32), last: (i32, i32)) -> Self {
+        Self {
+            m_contig: fr.m_left_gp.contig,
+            m_gene: fr.m_left_gene.m_name.clone(),
+            m_chr: fr.m_left_gene.m_chr.clone(),
+            m_first_exon: first.0,
+            m_last_exon: last.0,
+            m_acceptor_pos: first.1,
+            m_donor_pos: last.1,
+            m_unique: fr.m_unique,
+            m_reads: fr.m_matches.len(),
+            m_acceptor_linear_reads: 0,
+            m_donor_linear_reads: 0,
+        }
+    }
+
+    pub(crate) fn same_circle(&self, other: &CircRna) -> bool {
+        self.m_contig == other.m_contig
+            && self.m_first_exon == other.m_first_exon
+            && self.m_last_exon == other.m_last_exon
+    }
+
+    pub(crate) fn linear_reads(&self) -> f64 {
+        (self.m_acceptor_linear_reads + self.m_donor_linear_reads) as f64 / 2.0
+    }
+
+    /// back-splice reads per linear read at the same splice sites, `None` without linear reads
+    pub(crate) fn circular_linear_ratio(&self) -> Option<f64> {
+        let linear = self.linear_reads();
+        if linear > 0.0 {
+            Some(self.m_reads as f64 / linear)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn exons(&self) -> String {
+        if self.m_first_exon == self.m_last_exon {
+            self.m_first_exon.to_string()
+        } else {
+            format!("{}-{}", self.m_first_exon, self.m_last_exon)
+        }
+    }
+
+    pub(crate) fn id(&self) -> String {
+        format!("circ{}_exon{}", self.m_gene, self.exons())
+    }
+
+    pub(crate) fn title(&self) -> String {
+        let plural = if self.m_first_exon == self.m_last_exon { "" } else { "s" };
+        format!("{} circRNA of exon{} {}", self.m_gene, plural, self.exons())
+    }
+
+    /// the ratio as text, `NA` without linear reads
+    pub(crate) fn ratio_str(&self) -> String {
+        match self.circular_linear_ratio() {
+            Some(r) => format!("{:.3}", r),
+            None => "NA".to_string(),
+        }
+    }
+
+    pub(crate) fn print(&self) {
+        println!(
+            "#CircRNA: {}  (unique: {}, total: {}, linear: {}|{}, circular/linear: {})",
+            self.title(),
+            self.m_unique,
+            self.m_reads,
+            self.m_acceptor_linear_reads,
+            self.m_donor_linear_reads,
+            self.ratio_str()
+        );
+        println!("{}:{} - {}:{}", self.m_chr, self.m_donor_pos, self.m_chr, self.m_acceptor_pos);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn circle_and_ratio() {
+        let mut circ = CircRna {
+            m_contig: 0,
+            m_gene: "HIPK3".to_string(),
+            m_chr: "chr11".to_string(),
+            m_first_exon: 2,
+            m_last_exon: 2,
+            m_acceptor_pos: 33307959,
+            m_donor_pos: 33309057,
+            m_unique: 9,
+            m_reads: 12,
+            m_acceptor_linear_reads: 0,
+            m_donor_linear_reads: 0,
+        };
+        assert_eq!(circ.id(), "circHIPK3_exon2");
+        assert_eq!(circ.title(), "HIPK3 circRNA of exon 2");
+        assert_eq!(circ.circular_linear_ratio(), None);
+        assert_eq!(circ.ratio_str(), "NA");
+
+        circ.m_last_exon = 3;
+        circ.m_acceptor_linear_reads = 10;
+        circ.m_donor_linear_reads = 14;
+        assert_eq!(circ.title(), "HIPK3 circRNA of exons 2-3");
+        assert_eq!(circ.circular_linear_ratio(), Some(1.0));
+    }
+}
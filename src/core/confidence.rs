@@ -0,0 +1,91 @@
+// unique reads giving half of the full support score
+const HALF_SUPPORT_UNIQUE: f64 = 3.0;
+
+// weights of the components in the score, in the order of Confidence::components()
+const WEIGHTS: [f64; 6] = [0.30, 0.15, 0.15, 0.20, 0.10, 0.10];
+
+/// Evidence of one fusion, the raw values behind its confidence score.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Confidence {
+    pub(crate) unique: i32,
+    // left plus right edit distance, averaged over reads
+    pub(crate) mean_edit_distance: f64,
+    // the shorter segment of each read as a fraction of the read, averaged over reads
+    pub(crate) mean_short_segment: f64,
+    // k-mers of the reference at both sides of the break, a k-mer with n copies counting 1/n
+    pub(crate) kmer_unique_ratio: f64,
+    pub(crate) forward_reads: usize,
+    pub(crate) reversed_reads: usize,
+    // sides of the junction at an exon boundary, 0 to 2
+    pub(crate) exon_boundaries: u8,
+}
+
+impl Confidence {
+    /// every component scaled to 0~1, higher is better
+    pub(crate) fn components(&self, diff_threshold: i32) -> [(&'static str, f64); 6] {
+        let unique = self.unique.max(0) as f64;
+        let reads = (self.forward_reads + self.reversed_reads) as f64;
+
+        [
+            ("support", unique / (unique + HALF_SUPPORT_UNIQUE)),
+            (
+                "edit_distance",
+                (1.0 - self.mean_edit_distance / diff_threshold.max(1) as f64).clamp(0.0, 1.0),
+            ),
+            // a segment of half of the read is the best
+            ("segment_length", (self.mean_short_segment * 2.0).clamp(0.0, 1.0)),
+            ("kmer_uniqueness", self.kmer_unique_ratio.clamp(0.0, 1.0)),
+            (
+                "strand_balance",
+                if reads > 0.0 {
+                    2.0 * self.forward_reads.min(self.reversed_reads) as f64 / reads
+                } else {
+                    0.0
+                },
+            ),
+            ("exon_boundary", self.exon_boundaries as f64 / 2.0),
+        ]
+    }
+
+    /// weighted mean of the components, 0~1
+    pub(crate) fn score(&self, diff_threshold: i32) -> f64 {
+        self.components(diff_threshold)
+            .iter()
+            .zip(WEIGHTS.iter())
+            .map(|((_, c), w)| c * w)
+            .sum::<f64>()
+            / WEIGHTS.iter().sum::<f64>()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn components_and_score() {
+        let best = Confidence {
+            unique: 1000,
+            mean_edit_distance: 0.0,
+            mean_short_segment: 0.5,
+            kmer_unique_ratio: 1.0,
+            forward_reads: 500,
+            reversed_reads: 500,
+            exon_boundaries: 2,
+        };
+        assert!(best.score(5) > 0.99);
+
+        let weak = Confidence {
+            unique: 3,
+            mean_edit_distance: 2.5,
+            mean_short_segment: 0.125,
+            kmer_unique_ratio: 0.5,
+            forward_reads: 3,
+            reversed_reads: 0,
+            exon_boundaries: 1,
+        };
+        let components = weak.components(5).map(|(_, c)| c);
+        assert_eq!(components, [0.5, 0.5, 0.25, 0.5, 0.0, 0.5]);
+        assert!((weak.score(5) - 0.4125).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use super::{
+    fusion_result::FusionResult, fusion_scan::Error, read::quality_color, read_match::ReadMatch,
+};
+
+// highest quality the FASTQ style string can carry
+const MAX_QUAL: i32 = 93;
+
+/// Junction sequence assembled from all reads of a fusion, aligned at their read breaks.
+///
+/// It covers every base any read has on either side of the junction. Each base carries the
+/// reads covering it (depth), the reads agreeing with it (support) and a Phred quality, the
+/// summed qualities of agreeing bases minus those of disagreeing ones.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct JunctionConsensus {
+    pub(crate) m_seq: String,
+    // Phred+33, like FASTQ
+    pub(crate) m_qual: String,
+    pub(crate) m_depth: Vec<u32>,
+    pub(crate) m_support: Vec<u32>,
+    // bases from the left gene, the junction is between m_break - 1 and m_break
+    pub(crate) m_break: usize,
+}
+
+impl JunctionConsensus {
+    pub(crate) fn from_matches(matches: &[ReadMatch]) -> Self {
+        let left = matches
+            .iter()
+            .map(|rm| rm.m_read_break + 1)
+            .max()
+            .unwrap_or(0)
+            .max(0) as usize;
+        let right = matches
+            .iter()
+            .map(|rm| rm.m_read.m_seq.m_str.len() as i32 - rm.m_read_break - 1)
+            .max()
+            .unwrap_or(0)
+            .max(0) as usize;
+
+        // summed quality and count of A, T, C and G at every position
+        let mut quals = vec![[0_i32; 4]; left + right];
+        let mut counts = vec![[0_u32; 4]; left + right];
+
+        for rm in matches.iter() {
+            let offset = left as i32 - (rm.m_read_break + 1);
+            let bases = rm.m_read.m_seq.m_str.as_bytes();
+            let qualities = rm.m_read.m_quality.as_bytes();
+
+            for (i, (base, qual)) in bases.iter().zip(qualities.iter()).enumerate() {
+                let pos = offset + i as i32;
+                if pos < 0 || pos as usize >= left + right {
+                    continue;
+                }
+
+                let b = match base {
+                    b'A' => 0,
+                    b'T' => 1,
+                    b'C' => 2,
+                    b'G' => 3,
+                    _ => continue,
+                };
+
+                quals[pos as usize][b] += (*qual as i32 - 33).max(0);
+                counts[pos as usize][b] += 1;
+            }
+        }
+
+        let mut cons = Self {
+            m_break: left,
+            ..Default::default()
+        };
+
+        for (q, c) in quals.iter().zip(counts.iter()) {
+            let depth = c.iter().sum::<u32>();
+
+            // the base with most quality, then most reads
+            let best = (0..4).max_by_key(|&b| (q[b], c[b], 3 - b)).unwrap();
+            let qual = if depth == 0 {
+                0
+            } else {
+                (2 * q[best] - q.iter().sum::<i32>()).clamp(0, MAX_QUAL)
+            };
+
+            cons.m_seq.push(if depth == 0 { 'N' } else { b"ATCG"[best] as char });
+            cons.m_qual.push((qual + 33) as u8 as char);
+            cons.m_depth.push(depth);
+            cons.m_support.push(c[best]);
+        }
+
+        cons
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.m_seq.is_empty()
+    }
+
+    /// bases colored by quality, depth, support and quality shown when the mouse is over a base
+    pub(crate) fn html_seq(&self, start: usize, end: usize) -> String {
+        let mut ss = String::new();
+
+        for i in start..end.min(self.m_seq.len()) {
+            let qual = self.m_qual.as_bytes()[i];
+            write!(
+                &mut ss,
+                "<a title='depth {}, support {}, Q{}'><font color='{}'>{}</font></a>",
+                self.m_depth[i],
+                self.m_support[i],
+                qual - 33,
+                quality_color(qual as char),
+                &self.m_seq[i..(i + 1)]
+            )
+            .unwrap();
+        }
+
+        ss
+    }
+}
+
+/// one record per fusion, the header carries the junction position and the depth range
+pub(crate) fn write_consensus_fasta(file: &str, results: &[FusionResult]) -> Result<(), Error> {
+    let mut f = BufWriter::new(File::create(file)?);
+
+    for fr in results.iter().filter(|fr| !fr.m_consensus.is_empty()) {
+        let cons = &fr.m_consensus;
+        writeln!(
+            f,
+            ">{} break={} length={} depth={}-{} reads={}",
+            fr.id(),
+            cons.m_break,
+            cons.m_seq.len(),
+            cons.m_depth.iter().min().unwrap(),
+            cons.m_depth.iter().max().unwrap(),
+            fr.m_matches.len()
+        )?;
+        writeln!(f, "{}", cons.m_seq)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::{common::GenePos, read::SequenceRead};
+
+    use super::*;
+
+    fn read_match(seq: &str, qual: &str, read_break: i32) -> ReadMatch<'static> {
+        let read = SequenceRead::new(
+            "@r".to_owned(),
+            seq.to_owned(),
+            "+".to_owned(),
+            qual.to_owned(),
+            true,
+        );
+
+        ReadMatch::new(read, read_break, GenePos::default(), GenePos::default(), 0, false)
+    }
+
+    #[test]
+    fn consensus_at_read_breaks() {
+        // the junction is between GACGT and TTGAC, the third read has a low quality error after it
+        let matches = [
+            read_match("GACGTTTG", "IIIIIIII", 4),
+            read_match("ACGTTTGA", "IIIIIIII", 3),
+            read_match("CGTATGAC", "III#IIII", 2),
+        ];
+
+        let cons = JunctionConsensus::from_matches(&matches);
+
+        assert_eq!(cons.m_break, 5);
+        assert_eq!(cons.m_seq, "GACGTTTGAC");
+        assert_eq!(cons.m_depth, vec![1, 2, 3, 3, 3, 3, 3, 3, 2, 1]);
+        assert_eq!(cons.m_support, vec![1, 2, 3, 3, 3, 2, 3, 3, 2, 1]);
+        // Q40 + Q40 - Q2
+        assert_eq!(cons.m_qual.as_bytes()[5], 78 + 33);
+        assert_eq!(cons.m_qual.as_bytes()[2], MAX_QUAL as u8 + 33);
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use super::{fusion_scan::Error, gene::Gene, transcript::{FrameType, ReadingFrame}};
+
+/// A protein domain, amino acid positions from the start codon, 1-based and both included.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Domain {
+    pub(crate) m_gene: String,
+    pub(crate) m_name: String,
+    pub(crate) m_start: usize,
+    pub(crate) m_end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Retention {
+    Retained,
+    Partial,
+    Lost,
+}
+
+impl Retention {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Retention::Retained => "retained",
+            Retention::Partial => "partially retained",
+            Retention::Lost => "lost",
+        }
+    }
+
+    // domain from `start` to `end` in a protein keeping amino acids `first` to `last`
+    fn of(start: usize, end: usize, first: usize, last: usize) -> Self {
+        if first <= start && end <= last {
+            Retention::Retained
+        } else if first <= end && start <= last {
+            Retention::Partial
+        } else {
+            Retention::Lost
+        }
+    }
+}
+
+/// What the fusion protein keeps of a domain of the 5' or the 3' partner.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DomainHit {
+    pub(crate) m_domain: Domain,
+    pub(crate) m_five_prime: bool,
+    pub(crate) m_retention: Retention,
+}
+
+impl DomainHit {
+    pub(crate) fn partner(&self) -> &'static str {
+        if self.m_five_prime {
+            "5'"
+        } else {
+            "3'"
+        }
+    }
+
+    /// `ALK kinase domain retained`
+    pub(crate) fn description(&self) -> String {
+        format!("{} {} {}", self.m_domain.m_gene, self.m_domain.m_name, self.m_retention.name())
+    }
+}
+
+/// Protein domains by gene, read from a TSV of gene or transcript, domain name, start and end.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct DomainTable {
+    pub(crate) m_domains: HashMap<String, Vec<Domain>>,
+}
+
+impl DomainTable {
+    pub(crate) fn read(file: &str) -> Result<Self, Error> {
+        let reader = BufReader::new(File::open(file)?);
+        let mut table = Self::default();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let cols = line.split('\t').map(|c| c.trim()).collect::<Vec<_>>();
+            if cols.len() < 4 {
+                return Err(format!("{}:{}: a domain line needs gene, domain, start and end columns", file, i + 1).into());
+            }
+            // a header without '#'
+            if i == 0 && cols[2].parse::<usize>().is_err() {
+                continue;
+            }
+
+            let domain = Domain {
+                m_gene: cols[0].to_owned(),
+                m_name: cols[1].to_owned(),
+                m_start: cols[2].parse()?,
+                m_end: cols[3].parse()?,
+            };
+            if domain.m_start == 0 || domain.m_start > domain.m_end {
+                return Err(format!("{}:{}: the domain {} ends before it starts", file, i + 1, domain.m_name).into());
+            }
+            table.m_domains.entry(domain.m_gene.clone()).or_default().push(domain);
+        }
+
+        for domains in table.m_domains.values_mut() {
+            domains.sort_unstable_by_key(|d| (d.m_start, d.m_end));
+        }
+
+        Ok(table)
+    }
+
+    /// domains of `gene`, named as in the gene list, like `ALK`, or with its transcript, like
+    /// `ALK_NM_004304`, where either part may be in the table
+    pub(crate) fn domains_of(&self, gene: &Gene) -> &[Domain] {
+        let name = gene.m_name.as_str();
+        let (symbol, transcript) = name.split_once('_').unwrap_or((name, name));
+
+        [name, symbol, transcript]
+            .iter()
+            .find_map(|key| self.m_domains.get(*key))
+            .map_or(&[], |d| d.as_slice())
+    }
+
+    /// Domains of both partners kept by the fusion protein of `frame`.
+    ///
+    /// The 5' partner keeps its complete codons up to the junction or to a premature stop. The 3'
+    /// partner keeps its domains only in frame, from the first complete codon after the junction
+    /// up to a premature stop, or from its own start codon when the 5' break is before any coding
+    /// sequence and the 3' break before the start codon. Partners without coding sequence are
+    /// left out.
+    pub(crate) fn annotate(&self, five: &Gene, three: &Gene, frame: &ReadingFrame) -> Vec<DomainHit> {
+        let mut hits = Vec::new();
+
+        if five.m_cds.is_some() {
+            let kept = frame.m_five_coding / 3;
+            let kept = frame.m_premature_stop.map_or(kept, |stop| kept.min(stop - 1));
+            hits.extend(self.domains_of(five).iter().map(|d| DomainHit {
+                m_domain: d.clone(),
+                m_five_prime: true,
+                m_retention: Retention::of(d.m_start, d.m_end, 1, kept),
+            }));
+        }
+
+        if three.m_cds.is_some() {
+            let (first, last) = if frame.m_three_intact && frame.m_five_coding == 0 {
+                (1, usize::MAX)
+            } else if frame.m_type == FrameType::InFrame {
+                let first = frame.m_three_skipped.div_ceil(3) + 1;
+                // fusion protein positions past the 5' partner, shifted to those of the 3' partner
+                let last = frame.m_premature_stop.map_or(usize::MAX, |stop| {
+                    (stop + frame.m_three_skipped / 3).saturating_sub(frame.m_five_coding / 3 + 1)
+                });
+                (first, last)
+            } else {
+                (usize::MAX, 0)
+            };
+            hits.extend(self.domains_of(three).iter().map(|d| DomainHit {
+                m_domain: d.clone(),
+                m_five_prime: false,
+                m_retention: Retention::of(d.m_start, d.m_end, first, last),
+            }));
+        }
+
+        hits
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn domain(gene: &str, name: &str, start: usize, end: usize) -> Domain {
+        Domain {
+            m_gene: gene.to_string(),
+            m_name: name.to_string(),
+            m_start: start,
+            m_end: end,
+        }
+    }
+
+    fn gene(name: &str) -> Gene {
+        let mut gene = Gene::new(name.to_string(), "chr2".to_string(), 0, 1000);
+        gene.m_cds = Some((0, 1000));
+        gene
+    }
+
+    #[test]
+    fn retained_domains() {
+        let mut table = DomainTable::default();
+        table.m_domains.insert("EML4".to_string(), vec![domain("EML4", "coiled coil", 14, 63), domain("EML4", "WD40", 400, 700)]);
+        table.m_domains.insert("ALK".to_string(), vec![domain("ALK", "MAM", 480, 635), domain("ALK", "kinase domain", 1116, 1392)]);
+        let (eml4, alk) = (gene("EML4_NM_019063"), gene("ALK"));
+        assert_eq!(table.domains_of(&eml4).len(), 2);
+
+        // 300 codons of EML4 joined in frame to ALK after its first 1057 codons and a base
+        let frame = ReadingFrame {
+            m_type: FrameType::InFrame,
+            m_five_coding: 901,
+            m_three_skipped: 3172,
+            ..Default::default()
+        };
+        let hits = table.annotate(&eml4, &alk, &frame);
+        let described = hits.iter().map(|h| (h.partner(), h.description())).collect::<Vec<_>>();
+        assert_eq!(
+            described,
+            vec![
+                ("5'", "EML4 coiled coil retained".to_string()),
+                ("5'", "EML4 WD40 lost".to_string()),
+                ("3'", "ALK MAM lost".to_string()),
+                ("3'", "ALK kinase domain retained".to_string()),
+            ]
+        );
+
+        // a stop at aa 400 of the fusion protein, aa 1157 of ALK
+        let frame = ReadingFrame {
+            m_premature_stop: Some(400),
+            ..frame
+        };
+        assert_eq!(table.annotate(&eml4, &alk, &frame)[3].m_retention, Retention::Partial);
+        // or within the coiled coil
+        let frame = ReadingFrame {
+            m_premature_stop: Some(20),
+            ..frame
+        };
+        assert_eq!(table.annotate(&eml4, &alk, &frame)[0].m_retention, Retention::Partial);
+
+        // out of frame the 3' partner keeps nothing, before its start codon it keeps all
+        let frame = ReadingFrame {
+            m_type: FrameType::OutOfFrame,
+            m_premature_stop: None,
+            ..frame
+        };
+        assert_eq!(table.annotate(&eml4, &alk, &frame)[3].m_retention, Retention::Lost);
+        let frame = ReadingFrame {
+            m_type: FrameType::Utr5,
+            m_three_intact: true,
+            ..Default::default()
+        };
+        let hits = table.annotate(&eml4, &alk, &frame);
+        assert_eq!(hits[0].m_retention, Retention::Lost);
+        assert_eq!(hits[3].m_retention, Retention::Retained);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::aux::global_settings::global_settings;
+
+/// What joined the two sides, told by the order and orientation of the segments on one chromosome.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum EventType {
+    // the sides are on different chromosomes
+    #[default]
+    Fusion,
+    Deletion,
+    TandemDuplication,
+    Inversion,
+}
+
+impl EventType {
+    /// The type and size of an event joining `left` with `right`, signed positions on the same chromosome.
+    ///
+    /// Positions increase along the fusion on either strand, so a jump forward skips the bases
+    /// between the breaks and a jump back repeats them. Sides on opposite strands are inverted.
+    pub(crate) fn classify(left: i32, right: i32) -> (EventType, i32) {
+        if (left < 0) != (right < 0) {
+            (EventType::Inversion, (right.abs() - left.abs()).abs())
+        } else if right > left {
+            (EventType::Deletion, right - left - 1)
+        } else {
+            (EventType::TandemDuplication, left - right + 1)
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            EventType::Fusion => "fusion",
+            EventType::Deletion => "deletion",
+            EventType::TandemDuplication => "tandem_duplication",
+            EventType::Inversion => "inversion",
+        }
+    }
+
+    /// the prefix of titles
+    pub(crate) fn title(&self) -> &'static str {
+        match self {
+            EventType::Fusion => "Fusion",
+            EventType::Deletion => "Deletion",
+            EventType::TandemDuplication => "Tandem duplication",
+            EventType::Inversion => "Inversion",
+        }
+    }
+
+    /// events on one gene smaller than this are removed
+    pub(crate) fn size_threshold(&self) -> usize {
+        let gs = global_settings();
+        match self {
+            EventType::Fusion => 0,
+            EventType::Deletion => gs.deletion_threshold,
+            EventType::TandemDuplication => gs.duplication_threshold,
+            EventType::Inversion => gs.inversion_threshold,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classify_by_order_and_orientation() {
+        // forward strand, 1000 joined to 1500 skips 499 bases, joined to 900 repeats 101
+        assert_eq!(EventType::classify(1000, 1500), (EventType::Deletion, 499));
+        assert_eq!(EventType::classify(1000, 900), (EventType::TandemDuplication, 101));
+        // the same events read on the reverse strand
+        assert_eq!(EventType::classify(-1500, -1000), (EventType::Deletion, 499));
+        assert_eq!(EventType::classify(-900, -1000), (EventType::TandemDuplication, 101));
+        assert_eq!(EventType::classify(1000, -1800), (EventType::Inversion, 800));
+        assert_eq!(EventType::classify(-1800, 1000), (EventType::Inversion, 800));
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use crate::aux::global_settings::global_settings;
+
+use super::fusion_scan::Error;
+
+/// Reference regions the matcher does not index, so that reads placed only there
+/// (alt contigs, decoys, processed pseudogenes...) are not removed as alignable.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ExcludeRegions {
+    // 0-based half-open intervals per contig, sorted and merged
+    m_regions: HashMap<String, Vec<(usize, usize)>>,
+    m_contig_patterns: Vec<String>,
+}
+
+impl ExcludeRegions {
+    pub(crate) fn from_global_settings() -> Result<Self, Error> {
+        let gs = global_settings();
+
+        let mut er = Self {
+            m_regions: HashMap::new(),
+            m_contig_patterns: gs.exclude_contigs.clone(),
+        };
+
+        if !gs.exclude_bed_file.is_empty() {
+            er.read_bed(&gs.exclude_bed_file)?;
+        }
+
+        Ok(er)
+    }
+
+    fn read_bed(&mut self, file: &str) -> Result<(), Error> {
+        let reader = BufReader::new(File::open(file)?);
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("track")
+                || line.starts_with("browser")
+            {
+                continue;
+            }
+
+            let mut cols = line.split('\t');
+            let (chr, start, end) = match (cols.next(), cols.next(), cols.next()) {
+                (Some(chr), Some(start), Some(end)) => (chr, start, end),
+                _ => {
+                    return Err(format!(
+                        "{}:{}: a BED line needs chrom, start and end columns",
+                        file,
+                        i + 1
+                    )
+                    .into())
+                }
+            };
+
+            let start = start.trim().parse::<usize>()?;
+            let end = end.trim().parse::<usize>()?;
+            if end <= start {
+                continue;
+            }
+
+            self.add_region(chr, start, end);
+        }
+
+        self.merge_regions();
+
+        Ok(())
+    }
+
+    fn add_region(&mut self, chr: &str, start: usize, end: usize) {
+        self.m_regions
+            .entry(chr.to_owned())
+            .or_default()
+            .push((start, end));
+    }
+
+    fn merge_regions(&mut self) {
+        for regions in self.m_regions.values_mut() {
+            regions.sort_unstable();
+
+            let mut merged: Vec<(usize, usize)> = Vec::with_capacity(regions.len());
+            for &(start, end) in regions.iter() {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                    _ => merged.push((start, end)),
+                }
+            }
+
+            *regions = merged;
+        }
+    }
+
+    /// the whole contig is skipped by the matcher
+    pub(crate) fn is_contig_excluded(&self, contig: &str) -> bool {
+        self.m_contig_patterns
+            .iter()
+            .any(|p| glob_match(p.as_bytes(), contig.as_bytes()))
+    }
+
+    /// overwrite excluded bases with `N`, no k-mer covering them is indexed then.
+    pub(crate) fn mask(&self, contig: &str, seq: &mut [u8]) {
+        if let Some(regions) = self.m_regions.get(contig) {
+            for &(start, end) in regions.iter() {
+                if start >= seq.len() {
+                    break;
+                }
+
+                let end = end.min(seq.len());
+                seq[start..end].fill(b'N');
+            }
+        }
+    }
+}
+
+/// shell style pattern, `*` matches any run of characters and `?` any single one.
+pub(crate) fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` in pattern and the text position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((sp, st)) = star {
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn contig_patterns() {
+        let er = ExcludeRegions {
+            m_regions: HashMap::new(),
+            m_contig_patterns: vec!["*_alt".to_owned(), "chrUn_*".to_owned(), "hs37d?".to_owned()],
+        };
+
+        assert!(er.is_contig_excluded("chr6_GL000251v2_alt"));
+        assert!(er.is_contig_excluded("chrUn_KI270302v1"));
+        assert!(er.is_contig_excluded("hs37d5"));
+        assert!(!er.is_contig_excluded("chr1"));
+        assert!(!er.is_contig_excluded("chr6_GL000251v2_alt_random"));
+        assert!(glob_match(b"*", b""));
+    }
+
+    #[test]
+    fn mask_merged_regions() {
+        let mut er = ExcludeRegions::default();
+        er.add_region("chr1", 2, 4);
+        er.add_region("chr1", 3, 6);
+        er.add_region("chr1", 8, 20);
+        er.merge_regions();
+
+        assert_eq!(er.m_regions["chr1"], vec![(2, 6), (8, 20)]);
+
+        let mut seq = b"ACGTACGTAC".to_vec();
+        er.mask("chr1", &mut seq);
+        assert_eq!(seq, b"ACNNNNGTNN");
+
+        er.mask("chr2", &mut seq);
+        assert_eq!(seq, b"ACNNNNGTNN");
+    }
+}
@@ -0,0 +1,150 @@
+use super::{fusion_result::FusionResult, gene::Gene};
+
+/// Exons joined by a junction within one gene, `(id, chromosome position of the boundary)` of the
+/// upstream and the downstream exon in transcript order, the same exon for a back-splice of one.
+///
+/// The left break must be at the end of an exon and the right break at the start of one, along
+/// the strand of the reads. Reads of either strand give the same pair.
+pub(crate) fn exon_junction(gene: &Gene, left_pos: i32, right_pos: i32) -> Option<((i32, i32), (i32, i32))> {
+    let left = gene.exon_ending_at(left_pos)?;
+    let right = gene.exon_starting_at(right_pos)?;
+    let left = (left.id, left_pos.abs() + gene.m_start);
+    let right = (right.id, right_pos.abs() + gene.m_start);
+
+    // the start of an exon comes first in transcript order
+    let order = |(id, pos): (i32, i32)| (id, if gene.is_reversed() { -pos } else { pos });
+    if order(left) <= order(right) {
+        Some((left, right))
+    } else {
+        Some((right, left))
+    }
+}
+
+/// Exons skipped by a junction of one gene, like MET exon 14 skipping or EGFRvIII.
+///
+/// The normal junctions of the exons at both sides are counted for comparison, the upstream
+/// exon with the next one and the downstream exon with the previous one.
+#[derive(Debug, Clone)]
+pub(crate) struct ExonSkip {
+    pub(crate) m_contig: i16,
+    pub(crate) m_gene: String,
+    pub(crate) m_chr: String,
+    pub(crate) m_left_exon: i32,
+    pub(crate) m_right_exon: i32,
+    pub(crate) m_left_pos: i32,
+    pub(crate) m_right_pos: i32,
+    pub(crate) m_unique: i32,
+    pub(crate) m_reads: usize,
+    pub(crate) m_left_normal_reads: usize,
+    pub(crate) m_right_normal_reads: usize,
+}
+
+impl ExonSkip {
+    pub(crate) fn new(fr: &FusionResult, left: (i32, i32), right: (i32, i32)) -> Self {
+        Self {
+            m_contig: fr.m_left_gp.contig,
+            m_gene: fr.m_left_gene.m_name.clone(),
+            m_chr: fr.m_left_gene.m_chr.clone(),
+            m_left_exon: left.0,
+            m_right_exon: right.0,
+            m_left_pos: left.1,
+            m_right_pos: right.1,
+            m_unique: fr.m_unique,
+            m_reads: fr.m_matches.len(),
+            m_left_normal_reads: 0,
+            m_right_normal_reads: 0,
+        }
+    }
+
+    pub(crate) fn same_junction(&self, other: &ExonSkip) -> bool {
+        self.m_contig == other.m_contig
+            && self.m_left_exon == other.m_left_exon
+            && self.m_right_exon == other.m_right_exon
+    }
+
+    /// exons skipped, `14` or `2-7`
+    pub(crate) fn skipped(&self) -> String {
+        if self.m_right_exon - self.m_left_exon == 2 {
+            (self.m_left_exon + 1).to_string()
+        } else {
+            format!("{}-{}", self.m_left_exon + 1, self.m_right_exon - 1)
+        }
+    }
+
+    pub(crate) fn id(&self) -> String {
+        format!("{}_exon{}_exon{}", self.m_gene, self.m_left_exon, self.m_right_exon)
+    }
+
+    pub(crate) fn title(&self) -> String {
+        let plural = if self.m_right_exon - self.m_left_exon > 2 { "s" } else { "" };
+        format!(
+            "{} exon{} {} skipping (exon {} joined to exon {})",
+            self.m_gene,
+            plural,
+            self.skipped(),
+            self.m_left_exon,
+            self.m_right_exon
+        )
+    }
+
+    pub(crate) fn print(&self) {
+        println!(
+            "#Exon skipping: {}  (unique: {}, total: {}, normal junctions: {}|{})",
+            self.title(),
+            self.m_unique,
+            self.m_reads,
+            self.m_left_normal_reads,
+            self.m_right_normal_reads
+        );
+        println!("{}:{} - {}:{}", self.m_chr, self.m_left_pos, self.m_chr, self.m_right_pos);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn skipped_exons_in_transcript_order() {
+        // exons 13 to 15 of a forward gene starting at 1000
+        let mut gene = Gene::new("MET".to_string(), "chr7".to_string(), 1000, 5000);
+        gene.add_exon(13, 1100, 1200);
+        gene.add_exon(14, 1500, 1600);
+        gene.add_exon(15, 1900, 2000);
+
+        // exon 13 joined to exon 15 on the forward strand, and the same read reverse complemented
+        let junction = ((13, 1200), (15, 1900));
+        assert_eq!(exon_junction(&gene, 200, 900), Some(junction));
+        assert_eq!(exon_junction(&gene, -900, -200), Some(junction));
+        // the normal junction, and a break inside an exon
+        assert_eq!(exon_junction(&gene, 200, 500), Some(((13, 1200), (14, 1500))));
+        assert_eq!(exon_junction(&gene, 210, 900), None);
+        // the end of exon 14 back to its start
+        assert_eq!(exon_junction(&gene, 600, 500), Some(((14, 1500), (14, 1600))));
+
+        // a reverse gene lists its exons from the end of the chromosome
+        let mut gene = Gene::new("EGFR".to_string(), "chr7".to_string(), 1000, 9000);
+        gene.add_exon(1, 8000, 8500);
+        gene.add_exon(2, 7000, 7100);
+        gene.add_exon(8, 2000, 2100);
+        assert!(gene.is_reversed());
+        // along the gene, the start of exon 1 joined to the end of exon 8
+        assert_eq!(exon_junction(&gene, -7000, -1100), Some(((1, 8000), (8, 2100))));
+
+        let skip = ExonSkip {
+            m_contig: 0,
+            m_gene: "EGFR".to_string(),
+            m_chr: "chr7".to_string(),
+            m_left_exon: 1,
+            m_right_exon: 8,
+            m_left_pos: 8000,
+            m_right_pos: 2100,
+            m_unique: 3,
+            m_reads: 4,
+            m_left_normal_reads: 0,
+            m_right_normal_reads: 0,
+        };
+        assert_eq!(skip.skipped(), "2-7");
+        assert_eq!(skip.title(), "EGFR exons 2-7 skipping (exon 1 joined to exon 8)");
+    }
+}
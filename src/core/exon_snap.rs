@@ -0,0 +1,114 @@
+use super::gene::Gene;
+
+/// the farthest a break is moved along identical sequence to reach an exon boundary
+pub(crate) const MAX_SNAP_SHIFT: i32 = 10;
+
+// the base at a signed gene position of the forward gene sequence, complemented on the reverse strand
+fn base_at(seq: &[u8], pos: i32) -> Option<u8> {
+    let b = *seq.get(pos.unsigned_abs() as usize)?;
+
+    Some(if pos >= 0 {
+        b
+    } else {
+        match b {
+            b'A' => b'T',
+            b'T' => b'A',
+            b'C' => b'G',
+            b'G' => b'C',
+            other => other,
+        }
+    })
+}
+
+/// Shifts of both breaks, within `MAX_SNAP_SHIFT`, giving the same fusion sequence.
+///
+/// Moving the breaks by `s > 0` takes `s` bases after the left break from the left gene
+/// instead of the right one, which gives the same sequence only if they are identical, like a
+/// microhomology at the junction. Positions increase along the fusion on either strand.
+pub(crate) fn equivalent_shifts(left_seq: &[u8], left_pos: i32, right_seq: &[u8], right_pos: i32) -> Vec<i32> {
+    let same = |l: i32, r: i32| match (base_at(left_seq, l), base_at(right_seq, r)) {
+        (Some(a), Some(b)) => a == b && a != b'N',
+        _ => false,
+    };
+
+    let forward = (1..=MAX_SNAP_SHIFT).take_while(|&s| same(left_pos + s, right_pos + s - 1));
+    let backward = (1..=MAX_SNAP_SHIFT)
+        .take_while(|&s| same(left_pos - s + 1, right_pos - s))
+        .map(|s| -s);
+
+    let mut shifts = vec![0];
+    shifts.extend(forward);
+    shifts.extend(backward);
+
+    shifts
+}
+
+/// sides of the junction at an exon boundary, the left break at the last base of an exon and
+/// the right break at the first one, along the fusion
+pub(crate) fn boundary_sides(left_gene: &Gene, left_pos: i32, right_gene: &Gene, right_pos: i32) -> u8 {
+    left_gene.is_exon_end(left_pos) as u8 + right_gene.is_exon_start(right_pos) as u8
+}
+
+/// The shift among `equivalent_shifts` putting most sides at an exon boundary, the smallest of
+/// them if several do, `0` if none does better than the breaks as they are.
+pub(crate) fn snap_shift(
+    left_gene: &Gene,
+    left_seq: &[u8],
+    left_pos: i32,
+    right_gene: &Gene,
+    right_seq: &[u8],
+    right_pos: i32,
+) -> i32 {
+    equivalent_shifts(left_seq, left_pos, right_seq, right_pos)
+        .into_iter()
+        .max_by_key(|&s| {
+            (
+                boundary_sides(left_gene, left_pos + s, right_gene, right_pos + s),
+                -s.abs(),
+            )
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::gene::Exon;
+
+    use super::*;
+
+    fn gene(start: i32, exons: &[(i32, i32)]) -> Gene {
+        Gene {
+            m_start: start,
+            m_exons: exons
+                .iter()
+                .enumerate()
+                .map(|(i, &(start, end))| Exon { id: i as i32 + 1, start, end })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn snap_through_microhomology() {
+        // the last 3 bases of the left exon (gene positions 8~10) are also the first of the right gene part
+        let left_seq = b"TTTTTTTTGCAAAAA";
+        let right_seq = b"CCCCCGCATTTTT";
+        let left_gene = gene(1000, &[(1002, 1010)]);
+        let right_gene = gene(2000, &[(2005, 2012)]);
+
+        // called at left 7, right 5: TTTTTTTT + GCATTTTT, the same as TTTTTTTTGCA + TTTTT
+        assert_eq!(equivalent_shifts(left_seq, 7, right_seq, 5), vec![0, 1, 2, 3]);
+        // the left exon ends at 10, the right exon starts at 5, only one of them can be reached
+        assert_eq!(boundary_sides(&left_gene, 7, &right_gene, 5), 1);
+        assert_eq!(snap_shift(&left_gene, left_seq, 7, &right_gene, right_seq, 5), 0);
+
+        // with the right exon starting at 8, both are reached
+        let right_gene = gene(2000, &[(2008, 2012)]);
+        assert_eq!(snap_shift(&left_gene, left_seq, 7, &right_gene, right_seq, 5), 3);
+
+        // reverse strands, positions decreasing along the forward gene
+        let left_rc = gene(1000, &[(1005, 1009)]);
+        assert!(left_rc.is_exon_end(-5));
+        assert_eq!(base_at(b"ACGT", -1), Some(b'G'));
+    }
+}
@@ -1,11 +1,12 @@
 use std::{
     borrow::Cow,
     cmp::{Ordering, Reverse},
+    collections::HashMap,
     error,
     sync::{Arc, Mutex},
 };
 
-use rayon::ThreadPool;
+use rayon::{prelude::*, ThreadPool};
 
 use crate::{
     aux::{
@@ -17,7 +18,7 @@ use crate::{
 };
 
 use super::{
-    edit_distance::edit_distance, fasta_reader::FastaReader, fusion::Fusion, fusion_result::FusionResult, fusion_scan::Error, indexer::{Indexer, SeqMatch}, matcher::Matcher, read::SequenceRead, read_match::ReadMatch, sequence::reverse_complement
+    breakpoint_cluster::{cluster_breakpoints, cluster_chain_breakpoints}, edit_distance::edit_distance, fasta_reader::FastaReader, fusion::Fusion, fusion_result::FusionResult, fusion_scan::Error, exclude_region::ExcludeRegions, indexer::{Indexer, SeqMatch}, matcher::{AlignableRegion, GFHasherBuilder, Matcher}, multi_fusion_result::MultiFusionResult, read::SequenceRead, event_type::EventType, exon_skip::{exon_junction, ExonSkip}, circ_rna::CircRna, domain::DomainTable, hotspot::Hotspot, known_fusion::{KnownFusions, KnownStatus}, pon::PanelOfNormals, reciprocal::{collapse_reciprocals, pair_reciprocals}, read_match::{ChainJunction, ReadMatch}, read_through::read_through_gap, rejection::{FilterStage, Rejection, Rejections}, spanning_pair::{reverse_complement_gp, SpanningPair}, allele_fraction::{forward_interval, WILD_TYPE_MAX_MISMATCHES}, common::GenePos, sequence::reverse_complement
 };
 
 pub(crate) struct FusionMapper<'s> {
@@ -27,6 +28,29 @@ pub(crate) struct FusionMapper<'s> {
     pub(crate) fusion_list: Vec<Fusion>,
     pub(crate) fusion_matches: Mutex<Vec<Vec<ReadMatch<'s>>>>,
     pub(crate) m_fusion_results: Vec<FusionResult<'s>>,
+    // reads chaining more than two segments
+    pub(crate) multi_matches: Mutex<Vec<ReadMatch<'s>>>,
+    pub(crate) m_multi_fusion_results: Vec<MultiFusionResult<'s>>,
+    // read pairs with one mate in each partner gene, indexed like fusion_matches
+    pub(crate) spanning_pairs: Mutex<Vec<Vec<SpanningPair>>>,
+    // forward intervals of reads lying in one gene, indexed by contig
+    pub(crate) wild_type_reads: Mutex<Vec<Vec<(i32, i32)>>>,
+    // known junctions genotyped while scanning
+    pub(crate) m_hotspots: Vec<Hotspot>,
+    // junctions of one gene skipping exons, reported apart from the fusions
+    pub(crate) m_exon_skips: Vec<ExonSkip>,
+    // back-splice junctions found in circRNA mode
+    pub(crate) m_circ_rnas: Vec<CircRna>,
+    // where the reads dropped by remove_alignables aligned to
+    pub(crate) m_alignable_regions: Vec<AlignableRegion>,
+    // tables given on the command line, read before scanning so that a bad file fails early
+    pub(crate) m_exclude: ExcludeRegions,
+    pub(crate) m_pon: Option<PanelOfNormals>,
+    pub(crate) m_domains: Option<DomainTable>,
+    pub(crate) m_known: Option<KnownFusions>,
+    // candidate reads before filtering and the reads dropped by each filter
+    pub(crate) m_candidate_count: usize,
+    pub(crate) m_rejections: Rejections,
 }
 
 impl<'s> FusionMapper<'s> {
@@ -43,19 +67,7 @@ impl<'s> FusionMapper<'s> {
         m_indexer.make_index();
         log::debug!("Made index.");
 
-        // init()
-        let m_fusion_match_size = fusion_list.len().pow(2);
-
-        let fusion_matches = Mutex::new(vec![vec![]; m_fusion_match_size]);
-
-        Ok(Self {
-            m_ref_file: ref_file.to_string(),
-            m_fusion_match_size: m_fusion_match_size as i32,
-            m_indexer,
-            fusion_list,
-            fusion_matches,
-            m_fusion_results: Vec::new(),
-        })
+        Self::with_indexer(ref_file.to_string(), fusion_list, m_indexer)
     }
 
     pub(crate) fn from_fasta_reader_and_fusion_files(
@@ -71,18 +83,46 @@ impl<'s> FusionMapper<'s> {
         m_indexer.make_index();
         log::debug!("Made index.");
 
+        let ref_file = m_indexer.m_reference.as_ref().unwrap().m_fasta_file.clone();
+        Self::with_indexer(ref_file, fusion_list, m_indexer)
+    }
+
+    // reads the tables given on the command line for the genes of `m_indexer`
+    fn with_indexer(m_ref_file: String, fusion_list: Vec<Fusion>, m_indexer: Indexer) -> Result<Self, Error> {
+        let m_hotspots = Self::load_hotspots(&fusion_list, &m_indexer)?;
+        let m_exclude = ExcludeRegions::from_global_settings()
+            .map_err(|e| format!("Failed to read the exclude regions: {}", e))?;
+        let m_pon = Self::load_pon()?;
+        let m_domains = Self::load_domains()?;
+        let m_known = Self::load_known_fusions()?;
+
         // init()
         let m_fusion_match_size = fusion_list.len().pow(2);
 
         let fusion_matches = Mutex::new(vec![vec![]; m_fusion_match_size]);
+        let wild_type_reads = Mutex::new(vec![vec![]; fusion_list.len()]);
 
         Ok(Self {
-            m_ref_file: m_indexer.m_reference.as_ref().unwrap().m_fasta_file.clone(),
+            m_ref_file,
             m_fusion_match_size: m_fusion_match_size as i32,
             m_indexer,
             fusion_list,
             fusion_matches,
             m_fusion_results: Vec::new(),
+            multi_matches: Mutex::new(Vec::new()),
+            m_multi_fusion_results: Vec::new(),
+            spanning_pairs: Mutex::new(vec![vec![]; m_fusion_match_size]),
+            wild_type_reads,
+            m_hotspots,
+            m_exon_skips: Vec::new(),
+            m_circ_rnas: Vec::new(),
+            m_alignable_regions: Vec::new(),
+            m_exclude,
+            m_pon,
+            m_domains,
+            m_known,
+            m_candidate_count: 0,
+            m_rejections: Rejections::from_global_settings(),
         })
     }
 
@@ -90,15 +130,23 @@ impl<'s> FusionMapper<'s> {
     /// - distance_req = 2
     ///
     /// - qual_req = 20
+    ///
+    /// `located` is set when the read lies in one gene only, at the gene position of its first base.
     pub(crate) fn map_read(
         &self,
         r: &SequenceRead,
         mapable: &mut bool,
+        located: &mut Option<GenePos>,
         distance_req: i32,
         qual_req: i32,
     ) -> Result<Option<ReadMatch<'s>>, Error> {
         let mut mapping = self.m_indexer.map_read(r);
 
+        *located = match mapping.as_slice() {
+            [single] => Some(single.start_gp.clone()),
+            _ => None,
+        };
+
         // if r.m_name.contains(DBT) {
         //     log::debug!("mapping={:#?}", mapping);
         // }
@@ -114,6 +162,11 @@ impl<'s> FusionMapper<'s> {
 
         *mapable = true;
 
+        // reads chaining more than two segments are handled as complex rearrangements
+        if mapping.len() > 2 {
+            return Ok(self.make_chain_match(r, &mut mapping));
+        }
+
         //if the left part of mapping result is reverse, use its reverse complement alternative and skip this one
         if !self.m_indexer.in_required_direction(&mapping) {
             // if r.m_name.contains(DBT) {
@@ -193,6 +246,59 @@ impl<'s> FusionMapper<'s> {
         Some(read_match)
     }
 
+    fn make_chain_match(
+        &self,
+        r: &SequenceRead,
+        mapping: &mut [SeqMatch],
+    ) -> Option<ReadMatch<'s>> {
+        let junctions = chain_junctions(mapping)?;
+
+        let first = junctions.first().unwrap();
+        let mut read_match = ReadMatch::new(
+            r.clone(),
+            first.m_read_break,
+            first.m_left_gp.clone(),
+            first.m_right_gp.clone(),
+            first.m_gap,
+            false,
+        );
+        read_match.m_junctions = junctions;
+
+        self.calc_chain_distance(&mut read_match);
+
+        Some(read_match)
+    }
+
+    fn calc_chain_distance(&self, m: &mut ReadMatch) -> () {
+        let seq = m.m_read.m_seq.m_str.as_str();
+
+        let mut distances = Vec::with_capacity(m.m_junctions.len() + 1);
+        let mut seg_start = 0_i32;
+        for j in m.m_junctions.iter() {
+            let len = j.m_read_break + 1 - seg_start;
+            distances.push(self.calc_ed(
+                &seq.subchars(seg_start as usize, len as usize),
+                j.m_left_gp.contig as i32,
+                j.m_left_gp.position - len + 1,
+                j.m_left_gp.position,
+            ));
+            seg_start = j.m_read_break + 1;
+        }
+
+        let last = m.m_junctions.last().unwrap();
+        let len = seq.len() as i32 - seg_start;
+        distances.push(self.calc_ed(
+            &seq.subchars(seg_start as usize, len as usize),
+            last.m_right_gp.contig as i32,
+            last.m_right_gp.position,
+            last.m_right_gp.position + len - 1,
+        ));
+
+        m.m_left_distance = *distances.first().unwrap();
+        m.m_right_distance = distances.iter().skip(1).sum();
+        m.m_segment_distances = distances;
+    }
+
     fn calc_distance(&self, m: &mut ReadMatch) -> () {
         let seq = m.m_read.m_seq.m_str.as_str();
 
@@ -251,6 +357,12 @@ impl<'s> FusionMapper<'s> {
     }
 
     pub(crate) fn add_match(&self, m: ReadMatch<'s>) -> () {
+        if m.is_multi_junction() {
+            log::debug!("add_match(), junctions={}", m.m_junctions.len());
+            self.multi_matches.lock().unwrap().push(m);
+            return;
+        }
+
         let left_contig = m.m_left_gp.contig;
         let right_contig = m.m_right_gp.contig;
 
@@ -273,6 +385,79 @@ impl<'s> FusionMapper<'s> {
             ),
         }
     }
+
+    /// `gp1` and `gp2` are the gene positions of the first bases of `r1` and `r2`,
+    /// the fragment is put in the fusion orientation the same way a split read is.
+    pub(crate) fn add_spanning_pair(&self, r1: &SequenceRead, gp1: GenePos, r2: &SequenceRead, gp2: GenePos) {
+        let len1 = r1.m_seq.m_str.len() as i32;
+        let len2 = r2.m_seq.m_str.len() as i32;
+
+        // the fragment reads R1 then the reverse complement of R2
+        let forward = [
+            SeqMatch { seq_start: 0, seq_end: len1 - 1, start_gp: gp1.clone() },
+            SeqMatch { seq_start: len1, seq_end: len1 + len2 - 1, start_gp: reverse_complement_gp(&gp2, len2) },
+        ];
+
+        let (left, left_len, right, right_len) = if self.m_indexer.in_required_direction(&forward) {
+            (gp1, len1, reverse_complement_gp(&gp2, len2), len2)
+        } else {
+            (gp2, len2, reverse_complement_gp(&gp1, len1), len1)
+        };
+
+        if left.contig == right.contig {
+            return;
+        }
+
+        let index = self.fusion_list.len() * right.contig as usize + left.contig as usize;
+
+        if let Some(v) = self.spanning_pairs.lock().unwrap().get_mut(index) {
+            v.push(SpanningPair {
+                m_name: r1.m_name.clone(),
+                m_left_gp: left,
+                m_left_len: left_len,
+                m_right_gp: right,
+                m_right_len: right_len,
+            });
+        }
+    }
+
+    /// Forward interval of a read lying in one gene from `gp`, `None` if it doesn't match the
+    /// gene contiguously, like a read crossing a junction with a segment too short to map.
+    pub(crate) fn wild_type_interval(&self, r: &SequenceRead, gp: &GenePos) -> Option<(i16, i32, i32)> {
+        let seq = r.m_seq.m_str.as_bytes();
+        let (start, end) = forward_interval(gp, seq.len() as i32);
+        let reference = self.m_indexer.m_fusion_seq.get(gp.contig as usize)?.as_bytes();
+        if start < 0 || end as usize >= reference.len() {
+            return None;
+        }
+        let reference = &reference[(start as usize)..=(end as usize)];
+
+        let complement = |b: u8| match b {
+            b'A' => b'T',
+            b'T' => b'A',
+            b'C' => b'G',
+            b'G' => b'C',
+            other => other,
+        };
+        let mismatches = if gp.position >= 0 {
+            seq.iter().zip(reference.iter()).filter(|(a, b)| a != b).count()
+        } else {
+            seq.iter().rev().zip(reference.iter()).filter(|(a, b)| complement(**a) != **b).count()
+        };
+
+        (mismatches <= WILD_TYPE_MAX_MISMATCHES).then_some((gp.contig, start, end))
+    }
+
+    /// reads found by `wild_type_interval`, added once per pack to hold the lock less
+    pub(crate) fn add_wild_type_reads(&self, reads: Vec<(i16, i32, i32)>) {
+        let mut wild_type_reads = self.wild_type_reads.lock().unwrap();
+        for (contig, start, end) in reads.into_iter() {
+            if let Some(v) = wild_type_reads.get_mut(contig as usize) {
+                v.push((start, end));
+            }
+        }
+    }
+
     pub(crate) fn filter_matches(&mut self, inner_thread_pool:Option<&ThreadPool>) -> () {
         // calc the sequence number before any filtering
         // let mut total = 0;
@@ -288,6 +473,11 @@ impl<'s> FusionMapper<'s> {
             .fold(0, |a, b| a + b.len());
 
         log::info!("sequence number before filtering: {}", total);
+        log::info!(
+            "chained sequence number before filtering: {}",
+            self.multi_matches.lock().unwrap().len()
+        );
+        self.m_candidate_count = total + self.multi_matches.lock().unwrap().len();
 
         self.remove_by_complexity();
         self.remove_by_distance();
@@ -296,35 +486,64 @@ impl<'s> FusionMapper<'s> {
     }
 
     fn remove_by_complexity(&mut self) -> () {
-        let mut removed = 0;
+        let mut rejections = Rejections::from_global_settings();
         for fm in self.fusion_matches.lock().unwrap().iter_mut() {
             fm.retain(|rm| {
                 let seq = rm.m_read.m_seq.m_str.as_str();
                 let read_break = rm.m_read_break;
 
-                let dec = is_low_complexity(&seq.subchars(0, (read_break + 1) as usize))
-                    || is_low_complexity(&seq.subchars(
-                        (read_break + 1) as usize,
-                        seq.len() - (read_break + 1) as usize,
-                    ));
-
-                if dec {
-                    removed += 1;
-                    false
+                let reason = if is_low_complexity(&seq.subchars(0, (read_break + 1) as usize)) {
+                    "left part is low complexity"
+                } else if is_low_complexity(&seq.subchars(
+                    (read_break + 1) as usize,
+                    seq.len() - (read_break + 1) as usize,
+                )) {
+                    "right part is low complexity"
                 } else {
-                    true
-                }
+                    return true;
+                };
+
+                self.reject(&mut rejections, rm, FilterStage::LowComplexity, reason.to_string());
+                false
             })
         }
 
-        log::info!("remove_by_complexity: {}", removed);
+        self.multi_matches.lock().unwrap().retain(|rm| {
+            let seq = rm.m_read.m_seq.m_str.as_str();
+            let mut start = 0;
+            let low_segment = rm
+                .segment_breaks()
+                .into_iter()
+                .chain([seq.len() as i32])
+                .position(|b| {
+                    let low = is_low_complexity(&seq.subchars(start, b as usize - start));
+                    start = b as usize;
+                    low
+                });
+
+            match low_segment {
+                Some(k) => {
+                    self.reject(
+                        &mut rejections,
+                        rm,
+                        FilterStage::LowComplexity,
+                        format!("segment {} is low complexity", k + 1),
+                    );
+                    false
+                }
+                None => true,
+            }
+        });
+
+        log::info!("remove_by_complexity: {}", rejections.len());
+        self.m_rejections.extend(rejections);
     }
 
     fn remove_by_distance(&mut self) -> () {
-        // diff should be less than DIFF_THRESHOLD
-        const DIFF_THRESHOLD: i32 = 5;
+        // diff should be less than diff_threshold
+        let diff_threshold = global_settings().diff_threshold;
 
-        let mut removed = 0;
+        let mut rejections = Rejections::from_global_settings();
         self.fusion_matches
             .lock()
             .unwrap()
@@ -332,10 +551,18 @@ impl<'s> FusionMapper<'s> {
             .for_each(|fm| {
                 {
                     fm.retain(|rm| {
-                        let dec = rm.m_left_distance + rm.m_right_distance >= DIFF_THRESHOLD;
+                        let dec = rm.m_left_distance + rm.m_right_distance >= diff_threshold;
 
                         if dec {
-                            removed += 1;
+                            self.reject(
+                                &mut rejections,
+                                rm,
+                                FilterStage::Distance,
+                                format!(
+                                    "edit distance {}+{} >= {}",
+                                    rm.m_left_distance, rm.m_right_distance, diff_threshold
+                                ),
+                            );
                             false
                         } else {
                             true
@@ -344,13 +571,34 @@ impl<'s> FusionMapper<'s> {
                 }
             });
 
+        self.multi_matches.lock().unwrap().retain(|rm| {
+            let distance = rm.m_segment_distances.iter().sum::<i32>();
+            let dec = distance >= diff_threshold;
+
+            if dec {
+                self.reject(
+                    &mut rejections,
+                    rm,
+                    FilterStage::Distance,
+                    format!("edit distance {} >= {}", distance, diff_threshold),
+                );
+                false
+            } else {
+                true
+            }
+        });
+        let removed = rejections.len();
+        self.m_rejections.extend(rejections);
+
         log::info!("removeByDistance: {}", removed);
     }
 
     fn remove_indels(&mut self) -> () {
-        // diff should be greather than INDEL_THRESHOLD
-        let INDEL_THRESHOLD = global_settings().deletion_threshold;
-        let mut removed = 0;
+        // every type of event on one gene has its own size threshold
+        let thresholds = [EventType::Deletion, EventType::TandemDuplication, EventType::Inversion]
+            .map(|et| (et, et.size_threshold() as i32));
+        let circrna_mode = global_settings().circrna_mode;
+        let mut rejections = Rejections::from_global_settings();
 
         self.fusion_matches
             .lock()
@@ -359,12 +607,33 @@ impl<'s> FusionMapper<'s> {
             .for_each(|fm| {
                 {
                     fm.retain(|rm| {
-                        let dec = rm.m_left_gp.contig == rm.m_right_gp.contig
-                            && (rm.m_left_gp.position - rm.m_right_gp.position).abs()
-                                < INDEL_THRESHOLD as i32;
+                        if rm.m_left_gp.contig != rm.m_right_gp.contig {
+                            return true;
+                        }
 
-                        if dec {
-                            removed += 1;
+                        let (event_type, size) = EventType::classify(rm.m_left_gp.position, rm.m_right_gp.position);
+                        // exon skipping is no small deletion whatever its size, nor a back-splice a duplication
+                        let gene = &self.fusion_list[rm.m_left_gp.contig as usize].m_gene;
+                        let spliced = event_type == EventType::Deletion
+                            || (circrna_mode && event_type == EventType::TandemDuplication);
+                        if spliced
+                            && gene.is_exon_end(rm.m_left_gp.position)
+                            && gene.is_exon_start(rm.m_right_gp.position)
+                        {
+                            return true;
+                        }
+                        let threshold = thresholds.iter().find(|(et, _)| *et == event_type).map_or(0, |(_, t)| *t);
+
+                        if size < threshold {
+                            self.reject(
+                                &mut rejections,
+                                rm,
+                                FilterStage::Indel,
+                                format!(
+                                    "{} of {} bp on the same gene < {}",
+                                    event_type.name(), size, threshold
+                                ),
+                            );
                             false
                         } else {
                             true
@@ -373,7 +642,8 @@ impl<'s> FusionMapper<'s> {
                 }
             });
 
-        log::info!("removeIndels: {}", removed);
+        log::info!("removeIndels: {}", rejections.len());
+        self.m_rejections.extend(rejections);
     }
 
     pub(crate) fn sort_matches(&mut self) {
@@ -383,6 +653,10 @@ impl<'s> FusionMapper<'s> {
             .unwrap().iter_mut().for_each(|rmv|{
                 rmv.sort_by(|a, b| b.partial_cmp(a).unwrap());
             });
+        self.multi_matches
+            .lock()
+            .unwrap()
+            .sort_by(|a, b| b.partial_cmp(a).unwrap());
             
 
 
@@ -394,9 +668,12 @@ impl<'s> FusionMapper<'s> {
     pub(crate) fn free_matches(&mut self) {
         // free it
         self.fusion_matches.lock().unwrap().clear();
+        self.multi_matches.lock().unwrap().clear();
+        self.spanning_pairs.lock().unwrap().clear();
+        self.wild_type_reads.lock().unwrap().clear();
     }
 
-    pub(crate) fn cluster_matches(&mut self) {
+    pub(crate) fn cluster_matches(&mut self, inner_thread_pool: Option<&ThreadPool>) {
         log::debug!("self.m_fusion_match_size={}", self.m_fusion_match_size);
         log::debug!(
             "fusion_matches_len={}",
@@ -404,6 +681,41 @@ impl<'s> FusionMapper<'s> {
         );
 
         let multi_csv_mode = MULTI_CSV_MODE.get().unwrap().clone();
+
+        let pon = self.m_pon.as_ref();
+        let domains = self.m_domains.as_ref();
+        let known = self.m_known.as_ref();
+        let rna_mode = global_settings().rna_mode;
+        let circrna_mode = global_settings().circrna_mode;
+
+        // supporting reads of the junctions of adjacent exons, by contig and upstream exon
+        let mut normal_junctions = HashMap::<(i16, i32), usize>::new();
+
+        let wild_type_reads = {
+            let mut wild_type_reads = std::mem::take(&mut *self.wild_type_reads.lock().unwrap());
+            wild_type_reads.iter_mut().for_each(|v| v.sort_unstable());
+            wild_type_reads
+        };
+
+        // gene pairs are clustered independently, a hot one doesn't hold the others
+        let clusters = {
+            let fusion_matches = self.fusion_matches.lock().unwrap();
+            let cluster_gene_pair = |fm: &Vec<ReadMatch<'s>>| {
+                let points = fm
+                    .iter()
+                    .map(|rm| (rm.m_left_gp.position, rm.m_right_gp.position))
+                    .collect::<Vec<_>>();
+                cluster_breakpoints(&points)
+            };
+
+            match inner_thread_pool {
+                Some(itp) => itp.install(|| fusion_matches.par_iter().map(cluster_gene_pair).collect::<Vec<_>>()),
+                None => fusion_matches.iter().map(cluster_gene_pair).collect::<Vec<_>>(),
+            }
+        };
+
+        // results of all gene pairs, annotated and filtered once reciprocal ones are collapsed
+        let mut candidates = Vec::<FusionResult>::new();
         for (i, fm) in (0..(self.m_fusion_match_size)).zip(
             self.fusion_matches
                 .lock()
@@ -412,22 +724,14 @@ impl<'s> FusionMapper<'s> {
                 .map(|e| e.as_slice()),
         ) {
             let mut frs = Vec::<FusionResult>::new();
-            for (m, rm) in (0..fm.len()).zip(fm.iter()) {
-                let mut found = false;
-                for (f, fr) in (0..frs.len()).zip(frs.iter_mut()) {
-                    if fr.support(rm) {
-                        fr.add_match(rm.clone());
-                        found = true;
-                        break;
-                    }
-                }
-                if !found {
-                    let mut fr = FusionResult::with_minimum();
-                    fr.add_match(rm.clone());
-                    frs.push(fr);
+            for (rm, &c) in fm.iter().zip(clusters[i as usize].iter()) {
+                // clusters are numbered in the order they are created
+                if c == frs.len() {
+                    frs.push(FusionResult::with_minimum());
                 }
+                frs[c].add_match(rm.clone());
             }
-            for (f, mut fr) in (0..frs.len()).zip(frs.into_iter()) {
+            for fr in frs.iter_mut() {
                 // log::debug!("init -> fr.m_left_ref_ext={} fr.m_right_ref={}", fr.m_left_ref_ext, fr.m_right_ref);
                 log::debug!("init -> fusion_list={:#?}", self.fusion_list);
                 fr.calc_fusion_point();
@@ -456,33 +760,271 @@ impl<'s> FusionMapper<'s> {
                 fr.adjust_fusion_break();
                 log::debug!("adjust_fusion_break -> fusion_list={:#?}", self.fusion_list);
                 // log::debug!("adjust_fusion_break -> fr.m_left_ref_ext={} fr.m_right_ref={}", fr.m_left_ref_ext, fr.m_right_ref);
+                if rna_mode {
+                    fr.snap_to_exon_boundaries(&self.fusion_list, &self.m_indexer.m_fusion_seq);
+                }
+            }
+
+            if rna_mode {
+                frs = Self::merge_same_breaks(frs);
+            }
+
+            for fr in frs.iter_mut() {
+                if rna_mode {
+                    // breaks may have moved and clusters may have been merged
+                    fr.make_reference(
+                        &self.m_indexer.m_fusion_seq[fr.m_left_gp.contig as usize],
+                        &self.m_indexer.m_fusion_seq[fr.m_right_gp.contig as usize],
+                    );
+                }
                 fr.calc_unique();
                 log::debug!("calc_unique -> fusion_list={:#?}", self.fusion_list);
                 // log::debug!("calc_unique -> fr.m_left_ref_ext={} fr.m_right_ref={}", fr.m_left_ref_ext, fr.m_right_ref);
+                fr.make_consensus();
+                fr.calc_break_context();
+            }
+
+            // breaks are final now, each spanning pair goes to the fusion giving the smallest insert
+            let spanning_pairs = std::mem::take(&mut self.spanning_pairs.lock().unwrap()[i as usize]);
+            for sp in spanning_pairs.into_iter() {
+                let best = frs
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(f, fr)| sp.insert_size(&fr.m_left_gp, &fr.m_right_gp).map(|insert| (insert, f)))
+                    .filter(|&(insert, _)| insert as usize <= global_settings().spanning_max_insert)
+                    .min();
+                if let Some((_, f)) = best {
+                    frs[f].m_spanning_pairs.push(sp);
+                }
+            }
+
+            for mut fr in frs.into_iter() {
                 fr.update_info(&self.fusion_list);
-                log::debug!("update_info -> fusion_list={:#?}", self.fusion_list);
-                // log::debug!("update_info -> fr.m_left_ref_ext={} fr.m_right_ref={}", fr.m_left_ref_ext, fr.m_right_ref);
-                if fr.is_qualified() {
-                    if !global_settings().output_deletions && fr.is_deletion() {
-                        continue;
+                candidates.push(fr);
+            }
+        }
+
+        // before the filters, so a partner too weak to be reported still adds its reads
+        if global_settings().collapse_reciprocal {
+            collapse_reciprocals(&mut candidates, global_settings().reciprocal_max_distance);
+            for fr in candidates.iter_mut() {
+                let Some(partner_unique) = fr.m_reciprocal.as_ref().map(|rp| rp.m_unique) else {
+                    continue;
+                };
+                // the title counts the reads of both junctions now
+                fr.update_info(&self.fusion_list);
+                fr.add_title_count("reciprocal", partner_unique);
+            }
+        }
+
+        for mut fr in candidates.into_iter() {
+            fr.calc_confidence(&self.m_indexer);
+            fr.calc_transcript(&self.m_indexer.m_fusion_seq);
+            if let Some(domains) = domains {
+                fr.calc_domains(domains);
+            }
+            if fr.is_left_protein_forward() == fr.is_right_protein_forward() {
+                let distance = global_settings().readthrough_distance;
+                fr.m_read_through = read_through_gap(fr.five_prime_gene(), fr.three_prime_gene(), &self.fusion_list, distance);
+            }
+            if let Some(known) = known {
+                fr.m_known = known.find(fr.five_prime_gene(), fr.three_prime_gene(), fr.junction_exons());
+            }
+            fr.calc_allele_fraction(&wild_type_reads);
+            if let Some(pon) = pon {
+                fr.m_pon = pon.find(&fr.breakpoints(), global_settings().pon_tolerance);
+                if let Some(hit) = &fr.m_pon {
+                    fr.add_title_count("pon", hit.m_samples as i32);
+                }
+            }
+            log::debug!("update_info -> fusion_list={:#?}", self.fusion_list);
+            // log::debug!("update_info -> fr.m_left_ref_ext={} fr.m_right_ref={}", fr.m_left_ref_ext, fr.m_right_ref);
+            let junction = match fr.m_event_type {
+                EventType::Deletion => exon_junction(&fr.m_left_gene, fr.m_left_gp.position, fr.m_right_gp.position),
+                _ => None,
+            };
+            let back_splice = match fr.m_event_type {
+                EventType::TandemDuplication if circrna_mode => {
+                    exon_junction(&fr.m_left_gene, fr.m_left_gp.position, fr.m_right_gp.position)
+                }
+                _ => None,
+            };
+            if let Some((left, right)) = junction {
+                if right.0 == left.0 + 1 {
+                    *normal_junctions.entry((fr.m_left_gp.contig, left.0)).or_default() += fr.m_matches.len();
+                }
+            }
+
+            if let Some(reason) = fr.unqualified_reason() {
+                let rejections = self.reject_all(&fr.m_matches, FilterStage::Unqualified, &reason);
+                self.m_rejections.extend(rejections);
+            } else {
+                if let Some((left, right)) = junction.filter(|(left, right)| right.0 > left.0 + 1) {
+                    let skip = ExonSkip::new(&fr, left, right);
+                    // reads of both strands may be clustered apart
+                    match self.m_exon_skips.iter_mut().find(|es| es.same_junction(&skip)) {
+                        Some(es) => {
+                            es.m_unique += skip.m_unique;
+                            es.m_reads += skip.m_reads;
+                        }
+                        None => self.m_exon_skips.push(skip),
                     }
-                    if fr.is_left_protein_forward() != fr.is_right_protein_forward() {
-                        if !global_settings().output_untranslated {
-                            continue;
+                    continue;
+                }
+                if let Some((first, last)) = back_splice {
+                    let circ = CircRna::new(&fr, first, last);
+                    match self.m_circ_rnas.iter_mut().find(|c| c.same_circle(&circ)) {
+                        Some(c) => {
+                            c.m_unique += circ.m_unique;
+                            c.m_reads += circ.m_reads;
                         }
+                        None => self.m_circ_rnas.push(circ),
                     }
-
-                    if !multi_csv_mode {
-                        fr.print(&self.fusion_list);
+                    continue;
+                }
+                if !global_settings().output_deletions && fr.is_intra_gene_indel() {
+                    let rejections = self.reject_all(&fr.m_matches, FilterStage::NotOutput, fr.m_event_type.name());
+                    self.m_rejections.extend(rejections);
+                    continue;
+                }
+                if global_settings().pon_remove && fr.m_pon.is_some() {
+                    let rejections = self.reject_all(&fr.m_matches, FilterStage::NotOutput, "panel of normals");
+                    self.m_rejections.extend(rejections);
+                    continue;
+                }
+                if global_settings().readthrough_remove && fr.m_read_through.is_some() {
+                    let rejections = self.reject_all(&fr.m_matches, FilterStage::NotOutput, "read-through");
+                    self.m_rejections.extend(rejections);
+                    continue;
+                }
+                if global_settings().known_only && fr.m_known.m_status == KnownStatus::Novel {
+                    let rejections = self.reject_all(&fr.m_matches, FilterStage::NotOutput, "novel");
+                    self.m_rejections.extend(rejections);
+                    continue;
+                }
+                if fr.is_left_protein_forward() != fr.is_right_protein_forward() {
+                    if !global_settings().output_untranslated {
+                        let rejections = self.reject_all(&fr.m_matches, FilterStage::NotOutput, "untranslated");
+                        self.m_rejections.extend(rejections);
+                        continue;
                     }
-                    
-                    self.m_fusion_results.push(fr);
                 }
+
+                if !multi_csv_mode {
+                    fr.print(&self.fusion_list);
+                }
+                
+                self.m_fusion_results.push(fr);
             }
         }
 
         self.sort_fusion_results();
+        {
+            let gs = global_settings();
+            pair_reciprocals(&mut self.m_fusion_results, gs.reciprocal_max_distance);
+        }
         log::info!("found {} fusions", self.m_fusion_results.len(),);
+
+        for es in self.m_exon_skips.iter_mut() {
+            es.m_left_normal_reads = normal_junctions.get(&(es.m_contig, es.m_left_exon)).copied().unwrap_or(0);
+            es.m_right_normal_reads = normal_junctions.get(&(es.m_contig, es.m_right_exon - 1)).copied().unwrap_or(0);
+        }
+        self.m_exon_skips.sort_by(|a, b| b.m_unique.cmp(&a.m_unique).then(b.m_reads.cmp(&a.m_reads)));
+        log::info!("found {} exon skipping junctions", self.m_exon_skips.len());
+
+        for c in self.m_circ_rnas.iter_mut() {
+            c.m_acceptor_linear_reads = normal_junctions.get(&(c.m_contig, c.m_first_exon - 1)).copied().unwrap_or(0);
+            c.m_donor_linear_reads = normal_junctions.get(&(c.m_contig, c.m_last_exon)).copied().unwrap_or(0);
+        }
+        self.m_circ_rnas.sort_by(|a, b| b.m_unique.cmp(&a.m_unique).then(b.m_reads.cmp(&a.m_reads)));
+        if circrna_mode {
+            log::info!("found {} circRNAs", self.m_circ_rnas.len());
+        }
+
+        if !multi_csv_mode {
+            for es in self.m_exon_skips.iter() {
+                es.print();
+            }
+            for c in self.m_circ_rnas.iter() {
+                c.print();
+            }
+            for hotspot in self.m_hotspots.iter() {
+                hotspot.print();
+            }
+        }
+
+        self.cluster_multi_matches(multi_csv_mode, inner_thread_pool);
+    }
+
+    fn cluster_multi_matches(&mut self, multi_csv_mode: bool, inner_thread_pool: Option<&ThreadPool>) {
+        // only reads through the same chain of contigs can support the same rearrangement
+        let mfrs = {
+            let multi_matches = self.multi_matches.lock().unwrap();
+            let mut chain_index = HashMap::with_hasher(GFHasherBuilder::new());
+            let mut chains = Vec::<Vec<&ReadMatch<'s>>>::new();
+            for rm in multi_matches.iter() {
+                let contigs = rm
+                    .m_junctions
+                    .iter()
+                    .map(|j| (j.m_left_gp.contig, j.m_right_gp.contig))
+                    .collect::<Vec<_>>();
+                let c = *chain_index.entry(contigs).or_insert(chains.len());
+                if c == chains.len() {
+                    chains.push(Vec::new());
+                }
+                chains[c].push(rm);
+            }
+
+            let cluster_chain = |chain: &Vec<&ReadMatch<'s>>| {
+                let breaks = chain.iter().map(|rm| rm.junction_breaks()).collect::<Vec<_>>();
+                cluster_chain_breakpoints(&breaks)
+            };
+            let clusters = match inner_thread_pool {
+                Some(itp) => itp.install(|| chains.par_iter().map(cluster_chain).collect::<Vec<_>>()),
+                None => chains.iter().map(cluster_chain).collect::<Vec<_>>(),
+            };
+
+            let mut mfrs = Vec::<MultiFusionResult>::new();
+            for (chain, clusters) in chains.iter().zip(clusters.iter()) {
+                let first = mfrs.len();
+                for (rm, &c) in chain.iter().zip(clusters.iter()) {
+                    // clusters are numbered in the order they are created
+                    if first + c == mfrs.len() {
+                        mfrs.push(MultiFusionResult::default());
+                    }
+                    mfrs[first + c].add_match((*rm).clone());
+                }
+            }
+
+            mfrs
+        };
+
+        for mut mfr in mfrs.into_iter() {
+            mfr.calc_junctions();
+            mfr.calc_unique();
+            mfr.update_info(&self.fusion_list);
+
+            if let Some(reason) = mfr.unqualified_reason() {
+                let rejections = self.reject_all(&mfr.m_matches, FilterStage::Unqualified, &reason);
+                self.m_rejections.extend(rejections);
+            } else {
+                if !multi_csv_mode {
+                    mfr.print();
+                }
+
+                self.m_multi_fusion_results.push(mfr);
+            }
+        }
+
+        self.m_multi_fusion_results.sort_by(|a, b| {
+            b.m_unique
+                .cmp(&a.m_unique)
+                .then(b.m_matches.len().cmp(&a.m_matches.len()))
+        });
+        log::info!(
+            "found {} complex rearrangements",
+            self.m_multi_fusion_results.len()
+        );
     }
 
     fn remove_alignables(&mut self, inner_thread_pool:Option<&ThreadPool>) -> () {
@@ -500,11 +1042,15 @@ impl<'s> FusionMapper<'s> {
                 seqs.push(rm.get_read().m_seq.clone());
             }
         }
+        for rm in self.multi_matches.lock().unwrap().iter() {
+            seqs.push(rm.get_read().m_seq.clone());
+        }
 
         log::info!("making matcher...");
-        let mut matcher = Matcher::from_ref_and_seqs(Arc::clone(self.m_indexer.m_reference.as_ref().unwrap()), &seqs, inner_thread_pool);
+        let mut matcher = Matcher::from_ref_and_seqs(Arc::clone(self.m_indexer.m_reference.as_ref().unwrap()), &seqs, &self.m_exclude, inner_thread_pool);
 
-        let mut removed = 0;
+        let mut rejections = Rejections::from_global_settings();
+        let mut region_counts = HashMap::with_hasher(GFHasherBuilder::new());
 
         log::info!("removing alignable sequences...");
         // second pass to remove alignable sequences
@@ -520,11 +1066,16 @@ impl<'s> FusionMapper<'s> {
                     
                     fm.retain(|rm| {
                         let mr = matcher.do_match(&rm.get_read().m_seq);
-                        let dec = mr.is_some();
 
                         // pb.inc(1);
-                        if dec {
-                            removed += 1;
+                        if let Some(mr) = mr {
+                            *region_counts.entry(matcher.match_region(&mr)).or_insert(0) += 1;
+                            self.reject(
+                                &mut rejections,
+                                rm,
+                                FilterStage::Alignable,
+                                format!("aligned to {}", matcher.match_pos_str(&mr)),
+                            );
                             false
                         } else {
                             true
@@ -537,13 +1088,162 @@ impl<'s> FusionMapper<'s> {
             });
 
             // pb.finish();
+
+            self.multi_matches.lock().unwrap().retain(|rm| {
+                if let Some(mr) = matcher.do_match(&rm.get_read().m_seq) {
+                    *region_counts.entry(matcher.match_region(&mr)).or_insert(0) += 1;
+                    self.reject(
+                        &mut rejections,
+                        rm,
+                        FilterStage::Alignable,
+                        format!("aligned to {}", matcher.match_pos_str(&mr)),
+                    );
+                    false
+                } else {
+                    true
+                }
+            });
         }
-        log::info!("removeAlignables: {}", removed);
+        log::info!("removeAlignables: {}", rejections.len());
+        self.m_rejections.extend(rejections);
+
+        self.m_alignable_regions = matcher.alignable_regions(region_counts);
+    }
+
+    fn reject(&self, rejections: &mut Rejections, rm: &ReadMatch, stage: FilterStage, reason: String) {
+        rejections.add(stage, || {
+            let gp_str = |gp: &GenePos| {
+                let gene = &self.fusion_list.get(gp.contig as usize).unwrap().m_gene;
+                format!(
+                    "{}:{}{}:{}",
+                    gene.m_name,
+                    if gp.position >= 0 { "+" } else { "-" },
+                    gene.m_chr,
+                    gene.gene_pos_2_chr_pos(gp.position).abs()
+                )
+            };
+
+            let candidate = if rm.is_multi_junction() {
+                rm.m_junctions
+                    .iter()
+                    .map(|j| format!("{}___{}", gp_str(&j.m_left_gp), gp_str(&j.m_right_gp)))
+                    .collect::<Vec<_>>()
+                    .join("~")
+            } else {
+                format!("{}___{}", gp_str(&rm.m_left_gp), gp_str(&rm.m_right_gp))
+            };
+
+            Rejection {
+                stage,
+                reason,
+                candidate,
+                read: rm.m_read.clone(),
+            }
+        });
+    }
+
+    /// every read of a dropped fusion result
+    fn reject_all(&self, matches: &[ReadMatch], stage: FilterStage, reason: &str) -> Rejections {
+        let mut rejections = Rejections::from_global_settings();
+        for rm in matches.iter() {
+            self.reject(&mut rejections, rm, stage, reason.to_string());
+        }
+
+        rejections
+    }
+
+    // snapping to exon boundaries may bring clusters of one junction to the same breaks
+    fn merge_same_breaks(frs: Vec<FusionResult<'s>>) -> Vec<FusionResult<'s>> {
+        let mut merged: Vec<FusionResult<'s>> = Vec::with_capacity(frs.len());
+
+        for fr in frs.into_iter() {
+            match merged
+                .iter_mut()
+                .find(|m| m.m_left_gp == fr.m_left_gp && m.m_right_gp == fr.m_right_gp)
+            {
+                Some(m) => {
+                    m.m_matches.extend(fr.m_matches);
+                    // calc_unique() compares neighbours
+                    m.m_matches.sort_by(|a, b| b.partial_cmp(a).unwrap());
+                }
+                None => merged.push(fr),
+            }
+        }
+
+        merged
+    }
+
+    fn load_hotspots(fusion_list: &[Fusion], indexer: &Indexer) -> Result<Vec<Hotspot>, Error> {
+        let file = global_settings().hotspot_file.clone();
+        if file.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let hotspots = Hotspot::read_file(&file, fusion_list, &indexer.m_fusion_seq)
+            .map_err(|e| format!("Failed to read the hotspots {}: {}", file, e))?;
+
+        Ok(hotspots)
+    }
+
+    /// `reads` are one fragment, a merged pair, both mates or a single-end read
+    pub(crate) fn genotype_hotspots(&self, reads: &[&SequenceRead]) {
+        for hotspot in self.m_hotspots.iter() {
+            hotspot.count_fragment(reads);
+        }
+    }
+
+    fn load_pon() -> Result<Option<PanelOfNormals>, Error> {
+        let file = global_settings().pon_file.clone();
+        if file.is_empty() {
+            return Ok(None);
+        }
+
+        let pon = PanelOfNormals::read(&file).map_err(|e| format!("Failed to read the panel of normals {}: {}", file, e))?;
+        log::info!("{} junctions of {} normal samples in the panel of normals", pon.m_junctions.len(), pon.m_total_samples);
+
+        Ok(Some(pon))
+    }
+
+    fn load_domains() -> Result<Option<DomainTable>, Error> {
+        let file = global_settings().domain_file.clone();
+        if file.is_empty() {
+            return Ok(None);
+        }
+
+        let table = DomainTable::read(&file).map_err(|e| format!("Failed to read the protein domains {}: {}", file, e))?;
+        log::info!("protein domains of {} genes", table.m_domains.len());
+
+        Ok(Some(table))
+    }
+
+    fn load_known_fusions() -> Result<Option<KnownFusions>, Error> {
+        let file = global_settings().known_fusions_file.clone();
+        if file.is_empty() {
+            return Ok(None);
+        }
+
+        let known = KnownFusions::read(&file).map_err(|e| format!("Failed to read the known fusions {}: {}", file, e))?;
+        log::info!("{} known fusions", known.m_fusions.len());
+
+        Ok(Some(known))
     }
 
     fn sort_fusion_results(&mut self) {
-        self.m_fusion_results
-            .sort_by(|a, b| Self::more_reads(b, a).unwrap()) // b,a instead of a,b because we want descending order.
+        if global_settings().sort_by_score {
+            self.m_fusion_results.sort_by(|a, b| {
+                b.m_score
+                    .total_cmp(&a.m_score)
+                    .then_with(|| Self::more_reads(b, a).unwrap())
+            });
+        } else {
+            self.m_fusion_results
+                .sort_by(|a, b| Self::more_reads(b, a).unwrap()) // b,a instead of a,b because we want descending order.
+        }
+
+        // the sort is stable, so read-through candidates keep their order
+        if global_settings().readthrough_demote {
+            self.m_fusion_results.sort_by_key(|fr| fr.m_read_through.is_some());
+        }
     }
 
     fn more_reads(r1: &FusionResult, r2: &FusionResult) -> Option<Ordering> {
@@ -556,12 +1256,52 @@ impl<'s> FusionMapper<'s> {
     }
 }
 
+/// junctions between the segments of a read in read order, `None` if the reverse complement of
+/// the read is to be used
+fn chain_junctions(mapping: &mut [SeqMatch]) -> Option<Vec<ChainJunction>> {
+    mapping.sort_by_key(|m| m.seq_start);
+
+    // like in_required_direction(), keep the direction where most segments are forward
+    let forward = mapping
+        .iter()
+        .filter(|m| m.start_gp.position >= 0)
+        .count();
+    if forward * 2 <= mapping.len() {
+        return None;
+    }
+
+    let junctions = mapping
+        .windows(2)
+        .map(|w| {
+            let (left, right) = (&w[0], &w[1]);
+            let read_break = (left.seq_end + right.seq_start) / 2;
+
+            ChainJunction {
+                m_read_break: read_break,
+                m_left_gp: GenePos {
+                    contig: left.start_gp.contig,
+                    position: left.start_gp.position + read_break,
+                },
+                m_right_gp: GenePos {
+                    contig: right.start_gp.contig,
+                    position: right.start_gp.position + read_break + 1,
+                },
+                m_gap: right.seq_start - left.seq_end - 1,
+            }
+        })
+        .collect();
+
+    Some(junctions)
+}
+
 fn is_low_complexity(s: &str) -> bool {
-    if s.len() < 20 {
+    let gs = global_settings();
+
+    if s.len() < gs.low_complexity_length {
         return true;
     }
 
-    if dis_connected_count(s) < 7 {
+    if dis_connected_count(s) < gs.low_complexity_discontinuity {
         return true;
     }
 
@@ -587,6 +1327,47 @@ mod test {
 
         let c = [0_u8; BLOOM_FILTER_SIZE];
     }
+    #[test]
+    fn three_segment_chain() {
+        let segment = |seq_start: i32, seq_end: i32, contig: i16, position: i32| SeqMatch {
+            seq_start,
+            seq_end,
+            start_gp: GenePos { contig, position },
+        };
+        // a reverse segment between two forward ones, the first starting at gene position 0
+        let mut mapping = [segment(80, 119, 2, 1920), segment(0, 39, 0, 0), segment(40, 79, 1, -600)];
+
+        let junctions = chain_junctions(&mut mapping).unwrap();
+        let breaks = junctions
+            .iter()
+            .map(|j| (j.m_read_break, j.m_left_gp.contig, j.m_left_gp.position, j.m_right_gp.contig, j.m_right_gp.position, j.m_gap))
+            .collect::<Vec<_>>();
+        assert_eq!(breaks, vec![(39, 0, 39, 1, -560, 0), (79, 1, -521, 2, 2000, 0)]);
+
+        // mostly reverse, the reverse complement of the read will be chained
+        let mut reversed = [segment(0, 39, 0, -100), segment(40, 79, 1, -600), segment(80, 119, 2, 1920)];
+        assert!(chain_junctions(&mut reversed).is_none());
+
+        let chained = |junctions: Vec<ChainJunction>| {
+            let read = SequenceRead::new("@r".to_string(), "A".repeat(120), "+".to_string(), "I".repeat(120), true);
+            let first = junctions[0].clone();
+            let mut rm = ReadMatch::new(read, first.m_read_break, first.m_left_gp, first.m_right_gp, first.m_gap, false);
+            rm.m_junctions = junctions;
+            rm
+        };
+        let mut gapped = junctions.clone();
+        gapped[1].m_gap = 2;
+        gapped[1].m_right_gp.position = 2003;
+
+        let mut mfr = MultiFusionResult::default();
+        mfr.add_match(chained(gapped));
+        mfr.add_match(chained(junctions));
+        mfr.calc_junctions();
+
+        assert_eq!(mfr.segment_count(), 3);
+        assert_eq!(mfr.m_junctions[1].m_right_gp.position, 2000);
+    }
+
     #[test]
     fn pcmp() {
         println!("{:?}", 1_i32.partial_cmp(&2).unwrap());
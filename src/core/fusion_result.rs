@@ -10,7 +10,9 @@ use crate::{
 use super::fusion_scan::Error;
 
 use super::{
-    common::GenePos, edit_distance, fusion::Fusion, gene::Gene, read, read_match::ReadMatch,
+    allele_fraction::{break_gap, count_spanning, AlleleFraction}, break_context::BreakContext, event_type::EventType, exon_snap::{boundary_sides, snap_shift}, common::GenePos, confidence::Confidence, consensus::JunctionConsensus, domain::{DomainHit, DomainTable}, edit_distance, fusion::Fusion, gene::Gene, indexer::Indexer, known_fusion::KnownHit, read, read_match::ReadMatch,
+    pon::{Breakpoints, PonHit}, reciprocal::ReciprocalPartner,
+    spanning_pair::{unique_spanning_pairs, SpanningPair}, transcript::{fusion_transcript, reading_frame, ReadingFrame},
 };
 use std::{
     error,
@@ -44,6 +46,32 @@ pub(crate) struct FusionResult<'s> {
     pub(crate) m_left_intron_num: f32,
     pub(crate) m_right_exon_num: f32,
     pub(crate) m_right_intron_num: f32,
+    // read pairs with a mate on each side of the junction, none crossing it
+    pub(crate) m_spanning_pairs: Vec<SpanningPair>,
+    pub(crate) m_consensus: JunctionConsensus,
+    // shared with the reciprocal partner
+    pub(crate) m_event_id: usize,
+    pub(crate) m_reciprocal: Option<ReciprocalPartner>,
+    pub(crate) m_confidence: Confidence,
+    pub(crate) m_score: f64,
+    pub(crate) m_allele_fraction: AlleleFraction,
+    // the junction of the panel of normals this fusion is at, if any
+    pub(crate) m_pon: Option<PonHit>,
+    // RNA mode only: how far the breaks were moved to exon boundaries and the sides at one
+    pub(crate) m_snap_shift: i32,
+    pub(crate) m_exon_boundary_sides: u8,
+    // bases deleted, duplicated or between the inverted breaks, 0 for a fusion
+    pub(crate) m_event_type: EventType,
+    pub(crate) m_event_size: i32,
+    pub(crate) m_break_context: BreakContext,
+    // the spliced fusion transcript and the bases of the 5' gene in it, empty if not transcribed
+    pub(crate) m_transcript: String,
+    pub(crate) m_transcript_break: usize,
+    pub(crate) m_reading_frame: ReadingFrame,
+    pub(crate) m_domains: Vec<DomainHit>,
+    pub(crate) m_known: KnownHit,
+    // bases between the genes of a read-through candidate
+    pub(crate) m_read_through: Option<i32>,
 }
 
 impl<'s> FusionResult<'s> {
@@ -104,17 +132,21 @@ impl<'s> FusionResult<'s> {
         }
     }
 
-    pub(crate) fn is_deletion(&self) -> bool {
-        if self.m_left_gp.contig == self.m_right_gp.contig {
-            if self.m_left_gp.position > 0 && self.m_right_gp.position > 0 {
-                return true;
-            }
-            if self.m_left_gp.position < 0 && self.m_right_gp.position < 0 {
-                return true;
-            }
-        }
+    /// a deletion or tandem duplication within one gene, only output with --output_deletions
+    pub(crate) fn is_intra_gene_indel(&self) -> bool {
+        self.m_left_gp.contig == self.m_right_gp.contig
+            && matches!(self.m_event_type, EventType::Deletion | EventType::TandemDuplication)
+    }
 
-        false
+    fn classify_event(&mut self) {
+        (self.m_event_type, self.m_event_size) = if self.m_left_gene.m_chr == self.m_right_gene.m_chr {
+            EventType::classify(
+                self.m_left_gene.gene_pos_2_chr_pos(self.m_left_gp.position),
+                self.m_right_gene.gene_pos_2_chr_pos(self.m_right_gp.position),
+            )
+        } else {
+            (EventType::Fusion, 0)
+        };
     }
 
     fn can_be_mapped(&self) -> bool {
@@ -160,37 +192,252 @@ impl<'s> FusionResult<'s> {
         false
     }
 
-    pub(crate) fn is_qualified(&self) -> bool {
-        if self.m_unique < global_settings().unique_requirement as i32 {
+    /// needs the final breaks, so call it after adjust_fusion_break()
+    pub(crate) fn make_consensus(&mut self) {
+        self.m_consensus = JunctionConsensus::from_matches(&self.m_matches);
+    }
+
+    /// needs the final references, so call it after make_reference()
+    pub(crate) fn calc_break_context(&mut self) {
+        self.m_break_context = BreakContext::new(
+            &self.m_left_ref,
+            &self.m_left_ref_ext,
+            &self.m_right_ref,
+            &self.m_right_ref_ext,
+        );
+    }
+
+    /// the gene coming first in the fusion transcript, the right one when read against the
+    /// transcription of both genes
+    pub(crate) fn five_prime_gene(&self) -> &Gene {
+        if self.is_left_protein_forward() {
+            &self.m_left_gene
+        } else {
+            &self.m_right_gene
+        }
+    }
+
+    pub(crate) fn three_prime_gene(&self) -> &Gene {
+        if self.is_left_protein_forward() {
+            &self.m_right_gene
+        } else {
+            &self.m_left_gene
+        }
+    }
+
+    /// the fusion transcript and its reading frame, needs the genes, so call it after update_info()
+    pub(crate) fn calc_transcript(&mut self, fusion_seq: &[String]) {
+        if self.is_left_protein_forward() != self.is_right_protein_forward() {
+            self.m_transcript.clear();
+            self.m_reading_frame = ReadingFrame::default();
+            return;
+        }
+
+        let bp = self.breakpoints();
+        let left = (&self.m_left_gene, &fusion_seq[self.m_left_gp.contig as usize], bp.left_pos.abs());
+        let right = (&self.m_right_gene, &fusion_seq[self.m_right_gp.contig as usize], bp.right_pos.abs());
+        let (five, three) = if self.is_left_protein_forward() { (left, right) } else { (right, left) };
+
+        (self.m_transcript, self.m_transcript_break) = fusion_transcript(five.0, five.1, five.2, three.0, three.1, three.2);
+        self.m_reading_frame = reading_frame(five.0, five.1, five.2, three.0, three.1, three.2);
+    }
+
+    /// the last exon of the 5' gene and the first exon of the 3' gene in the fusion transcript,
+    /// `None` for breaks outside the exons and introns
+    pub(crate) fn junction_exons(&self) -> Option<(i32, i32)> {
+        let left = (self.m_left_is_exon, self.m_left_exon_or_intron_id);
+        let right = (self.m_right_is_exon, self.m_right_exon_or_intron_id);
+        let ((_, five), (three_is_exon, three)) = if self.is_left_protein_forward() { (left, right) } else { (right, left) };
+        if five < 0 || three < 0 {
+            return None;
+        }
+
+        // intron n is followed by exon n + 1
+        Some((five, if three_is_exon { three } else { three + 1 }))
+    }
+
+    /// protein domains the fusion keeps, after calc_transcript()
+    pub(crate) fn calc_domains(&mut self, table: &DomainTable) {
+        self.m_domains = if self.m_transcript.is_empty() {
+            Vec::new()
+        } else {
+            table.annotate(self.five_prime_gene(), self.three_prime_gene(), &self.m_reading_frame)
+        };
+    }
+
+    /// chromosome positions the left and the right break can be at along the microhomology
+    pub(crate) fn break_ranges(&self) -> ((i32, i32), (i32, i32)) {
+        let range = |gene: &Gene, pos: i32| {
+            let (first, last) = self.m_break_context.break_range(pos);
+            (gene.gene_pos_2_chr_pos(first), gene.gene_pos_2_chr_pos(last))
+        };
+
+        (
+            range(&self.m_left_gene, self.m_left_gp.position),
+            range(&self.m_right_gene, self.m_right_gp.position),
+        )
+    }
+
+    /// chromosome positions of both breaks, as reported in JSON
+    pub(crate) fn breakpoints(&self) -> Breakpoints {
+        Breakpoints {
+            left_chr: self.m_left_gene.m_chr.clone(),
+            left_pos: self.m_left_gene.gene_pos_2_chr_pos(self.m_left_gp.position),
+            right_chr: self.m_right_gene.m_chr.clone(),
+            right_pos: self.m_right_gene.gene_pos_2_chr_pos(self.m_right_gp.position),
+        }
+    }
+
+    /// stable name of the fusion, e.g. `GA:+chrA:18158___GC:+chrC:32008`, set by update_info()
+    pub(crate) fn id(&self) -> String {
+        let side = |gene: &Gene, gp: &GenePos| {
+            format!(
+                "{}:{}{}:{}",
+                gene.m_name,
+                if gp.position >= 0 { "+" } else { "-" },
+                gene.m_chr,
+                gene.gene_pos_2_chr_pos(gp.position).abs()
+            )
+        };
+
+        format!(
+            "{}___{}",
+            side(&self.m_left_gene, &self.m_left_gp),
+            side(&self.m_right_gene, &self.m_right_gp)
+        )
+    }
+
+    /// needs the genes and exons, so call it after update_info()
+    pub(crate) fn calc_confidence(&mut self, indexer: &Indexer) {
+        let reads = self.m_matches.len().max(1) as f64;
+
+        // the references are in the fusion direction, the index has the genes forward
+        let forward_ref = |seq: &str, gp: &GenePos| {
+            if gp.position < 0 {
+                reverse_complement(seq)
+            } else {
+                seq.to_string()
+            }
+        };
+        let left_ratio = indexer.kmer_unique_ratio(&forward_ref(&self.m_left_ref, &self.m_left_gp));
+        let right_ratio = indexer.kmer_unique_ratio(&forward_ref(&self.m_right_ref, &self.m_right_gp));
+
+        self.m_confidence = Confidence {
+            unique: self.m_unique,
+            mean_edit_distance: self
+                .m_matches
+                .iter()
+                .map(|rm| (rm.m_left_distance + rm.m_right_distance) as f64)
+                .sum::<f64>()
+                / reads,
+            mean_short_segment: self
+                .m_matches
+                .iter()
+                .map(|rm| {
+                    let left = rm.m_read_break + 1;
+                    let right = rm.m_read.len() as i32 - left;
+                    left.min(right).max(0) as f64 / rm.m_read.len().max(1) as f64
+                })
+                .sum::<f64>()
+                / reads,
+            kmer_unique_ratio: left_ratio.min(right_ratio),
+            forward_reads: self.m_matches.iter().filter(|rm| !rm.m_reversed).count(),
+            reversed_reads: self.m_matches.iter().filter(|rm| rm.m_reversed).count(),
+            exon_boundaries: self.m_left_gene.is_exon_boundary(self.m_left_gp.position, 1) as u8
+                + self.m_right_gene.is_exon_boundary(self.m_right_gp.position, 1) as u8,
+        };
+        self.m_score = self.m_confidence.score(global_settings().diff_threshold);
+
+        if global_settings().rna_mode {
+            self.m_exon_boundary_sides = boundary_sides(
+                &self.m_left_gene,
+                self.m_left_gp.position,
+                &self.m_right_gene,
+                self.m_right_gp.position,
+            );
+            // junctions of transcripts are at exon boundaries, each side away from one costs a quarter
+            self.m_score *= 1.0 - 0.25 * (2 - self.m_exon_boundary_sides) as f64;
+        }
+    }
+
+    /// `wild_type_reads` are the forward intervals of reads lying in one gene, by contig, sorted
+    pub(crate) fn calc_allele_fraction(&mut self, wild_type_reads: &[Vec<(i32, i32)>]) {
+        let ref_reads = |gp: &GenePos, is_left: bool| {
+            wild_type_reads
+                .get(gp.contig as usize)
+                .map_or(0, |intervals| count_spanning(intervals, break_gap(gp, is_left)))
+        };
+
+        self.m_allele_fraction = AlleleFraction::new(
+            self.m_matches.len(),
+            ref_reads(&self.m_left_gp, true),
+            ref_reads(&self.m_right_gp, false),
+        );
+    }
+
+    /// append a count to the ones in parentheses at the end of the title
+    pub(crate) fn add_title_count(&mut self, name: &str, count: i32) {
+        if self.m_title.ends_with(')') {
+            self.m_title.pop();
+            write!(self.m_title, ", {}:{})", name, count).unwrap();
+        }
+    }
+
+    pub(crate) fn spanning_unique(&self) -> i32 {
+        unique_spanning_pairs(&self.m_spanning_pairs) as i32
+    }
+
+    /// why this result is not reported, `None` when it is qualified
+    pub(crate) fn unqualified_reason(&self) -> Option<String> {
+        let gs = global_settings();
+
+        let mut unique = self.m_unique;
+        if gs.count_spanning_pairs {
+            unique += self.spanning_unique();
+        }
+
+        if unique < gs.unique_requirement as i32 {
             log::debug!("m_unique={}", self.m_unique);
-            return false;
+            return Some(format!(
+                "unique reads {} < {}",
+                unique, gs.unique_requirement
+            ));
+        }
+
+        if self.m_score < gs.min_score {
+            return Some(format!("confidence score {:.3} < {}", self.m_score, gs.min_score));
         }
 
         if self.can_be_mapped() {
             log::debug!("can_be_mapped=true",);
-            return false;
+            return Some("references of both sides are similar".to_string());
         }
 
-        if self.m_left_ref.len() <= 30 || self.m_right_ref.len() <= 30 {
+        if self.m_left_ref.len() <= gs.min_ref_length || self.m_right_ref.len() <= gs.min_ref_length {
             log::debug!(
                 "self.m_left_ref.len(), self.m_right_ref.len() {}, {}",
                 self.m_left_ref.len(),
                 self.m_right_ref.len()
             );
-            return false;
+            return Some(format!(
+                "reference length {}|{} <= {}",
+                self.m_left_ref.len(),
+                self.m_right_ref.len(),
+                gs.min_ref_length
+            ));
         }
 
-        if dis_connected_count(&self.m_left_ref.subchars(self.m_left_ref.len() - 10, 10)) <= 2 {
+        if dis_connected_count(&self.m_left_ref.subchars(self.m_left_ref.len() - 10, 10)) <= gs.ref_flank_discontinuity {
             log::debug!("dis_connected_count 1",);
-            return false;
+            return Some("left reference flank is low complexity".to_string());
         }
 
-        if dis_connected_count(&self.m_right_ref.subchars(0, 10)) <= 2 {
+        if dis_connected_count(&self.m_right_ref.subchars(0, 10)) <= gs.ref_flank_discontinuity {
             log::debug!("dis_connected_count 2",);
-            return false;
+            return Some("right reference flank is low complexity".to_string());
         }
 
-        true
+        None
     }
 
     pub(crate) fn update_info(&mut self, fusions: &[Fusion]) -> () {
@@ -205,12 +452,10 @@ impl<'s> FusionResult<'s> {
             .m_gene
             .clone();
 
+        self.classify_event();
+
         let mut ss = String::new();
-        if self.is_deletion() {
-            ss.push_str("Deletion: ");
-        } else {
-            ss.push_str("Fusion: ");
-        }
+        write!(ss, "{}: ", self.m_event_type.title()).unwrap();
 
         write!(
             ss,
@@ -224,6 +469,11 @@ impl<'s> FusionResult<'s> {
 
         self.m_title = ss;
 
+        if !self.m_spanning_pairs.is_empty() {
+            self.add_title_count("spanning", self.spanning_unique());
+        }
+
+
         self.m_left_pos = self.m_left_gene.pos2str(self.m_left_gp.position).unwrap();
         self.m_right_pos = self.m_right_gene.pos2str(self.m_right_gp.position).unwrap();
 
@@ -323,6 +573,40 @@ impl<'s> FusionResult<'s> {
         self.m_matches = m_matches;
     }
 
+    /// Move the breaks to exon boundaries along sequence identical in both genes, which any of
+    /// the positions explains equally well. Returns whether they were moved, the references are
+    /// to be made again then.
+    pub(crate) fn snap_to_exon_boundaries(&mut self, fusions: &[Fusion], fusion_seq: &[String]) -> bool {
+        let (left, right) = (self.m_left_gp.contig as usize, self.m_right_gp.contig as usize);
+        let shift = snap_shift(
+            &fusions[left].m_gene,
+            fusion_seq[left].as_bytes(),
+            self.m_left_gp.position,
+            &fusions[right].m_gene,
+            fusion_seq[right].as_bytes(),
+            self.m_right_gp.position,
+        );
+        if shift == 0 {
+            return false;
+        }
+
+        self.m_snap_shift = shift;
+        self.m_left_gp.position += shift;
+        self.m_right_gp.position += shift;
+        for read_match in self.m_matches.iter_mut() {
+            read_match.m_read_break += shift;
+            read_match.m_left_gp.position += shift;
+            read_match.m_right_gp.position += shift;
+        }
+
+        true
+    }
+
+    /// both breaks at exon boundaries, as splicing makes them
+    pub(crate) fn is_canonical(&self) -> bool {
+        self.m_exon_boundary_sides == 2
+    }
+
     fn calc_ed(&self, m: &ReadMatch, shift: i32, left_ed: &mut i32, right_ed: &mut i32) -> i32 {
         let read_break = m.m_read_break + shift;
         let seq = m.m_read.m_seq.m_str.as_str();
@@ -413,36 +697,6 @@ impl<'s> FusionResult<'s> {
         self.m_matches.push(m);
     }
 
-    pub(crate) fn support(&self, m: &ReadMatch) -> bool {
-        for read_match in self.m_matches.iter() {
-            if self.support_same(m, read_match) {
-                return true;
-            }
-        }
-
-        false
-    }
-
-    fn support_same(&self, m1: &ReadMatch, m2: &ReadMatch) -> bool {
-        const T: i32 = 3;
-
-        if (m1.m_left_gp.position - m2.m_left_gp.position).abs() > T {
-            return false;
-        }
-        if (m1.m_right_gp.position - m2.m_right_gp.position).abs() > T {
-            return false;
-        }
-
-        if (m1.m_left_gp.contig != m2.m_left_gp.contig) {
-            return false;
-        }
-
-        if (m1.m_right_gp.contig != m2.m_right_gp.contig) {
-            return false;
-        }
-
-        true
-    }
     pub(crate) fn is_left_protein_forward(&self) -> bool {
         if self.m_left_gene.is_reversed() {
             self.m_left_gp.position < 0
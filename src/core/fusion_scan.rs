@@ -12,7 +12,7 @@ use std::{
 use rayon::{prelude::*, ThreadPoolBuilder};
 
 use crate::{
-    aux::{limited_bufreader::LimitedBufReader, pbar::prepare_pbar_force},
+    aux::{global_settings::global_settings, limited_bufreader::LimitedBufReader, pbar::prepare_pbar_force},
     core::{
         fasta_reader::FastaReader,
         fastq_reader::{FastqReader, FastqReaderPair},
@@ -21,7 +21,11 @@ use crate::{
     },
 };
 
-use super::{fusion::Fusion, pescanner::PairEndScanner};
+use super::{
+    consensus::write_consensus_fasta, fusion::Fusion, fusion_mapper::FusionMapper, index_stat::write_index_stats_tsv,
+    pescanner::PairEndScanner, rejection::write_rejected_reads, transcript::write_fusion_fasta,
+    tsv_reporter::write_fusions_tsv, vcf_reporter::write_vcf,
+};
 
 pub(crate) type Error = Box<dyn error::Error + Send + Sync>;
 
@@ -192,58 +196,18 @@ impl FusionScan {
         fusion_csv_paths: &[String],
     ) -> (Vec<String>, Vec<String>) {
         let hf = self.m_html_file.as_str();
-
-        let (hf_parent, hf_stem, hf_ext) = if !hf.is_empty() {
-            let hf = Path::new(hf);
-            let (hf_parent, hf_stem, hf_ext) = (
-                hf.parent().unwrap().to_str().unwrap(),
-                hf.file_stem().unwrap().to_str().unwrap(),
-                hf.extension().unwrap().to_str().unwrap(),
-            );
-            (hf_parent, hf_stem, hf_ext)
-        } else {
-            ("", "", "")
-        };
-
         let jf = self.m_json_file.as_str();
 
-        let (jf_parent, jf_stem, jf_ext) = if !jf.is_empty() {
-            let jf = Path::new(jf);
-            let (jf_parent, jf_stem, jf_ext) = (
-                jf.parent().unwrap().to_str().unwrap(),
-                jf.file_stem().unwrap().to_str().unwrap(),
-                jf.extension().unwrap().to_str().unwrap(),
-            );
-            (jf_parent, jf_stem, jf_ext)
-        } else {
-            ("", "", "")
-        };
-
         let mut html_file_vec = Vec::new();
         let mut json_file_vec = Vec::new();
 
         fusion_csv_paths.iter().for_each(|fc| {
-            let fc_stem = Path::new(fc).file_stem().unwrap().to_str().unwrap();
             if !hf.is_empty() {
-                html_file_vec.push(
-                    [hf_parent, &format!("{}_{}.{}", hf_stem, fc_stem, hf_ext)]
-                        .iter()
-                        .collect::<PathBuf>()
-                        .into_os_string()
-                        .into_string()
-                        .unwrap(),
-                );
+                html_file_vec.push(per_csv_file_name(hf, fc));
             }
 
             if !jf.is_empty() {
-                json_file_vec.push(
-                    [jf_parent, &format!("{}_{}.{}", jf_stem, fc_stem, jf_ext)]
-                        .iter()
-                        .collect::<PathBuf>()
-                        .into_os_string()
-                        .into_string()
-                        .unwrap(),
-                );
+                json_file_vec.push(per_csv_file_name(jf, fc));
             }
         });
 
@@ -330,6 +294,76 @@ impl FusionScan {
     }
 }
 
+/// name of an extra report file of a scan.
+/// in multi csv mode, every fusion csv gets its own file, e.g. `stats.tsv` -> `stats_panel1.tsv`.
+pub(crate) fn report_file_name(file: &str, fusion_csv: &str) -> String {
+    if file.is_empty() || !*MULTI_CSV_MODE.get().unwrap_or(&false) {
+        return file.to_string();
+    }
+
+    per_csv_file_name(file, fusion_csv)
+}
+
+/// the file reports of a scan other than the HTML and JSON ones, each written when its file is set
+pub(crate) fn write_reports(mapper: &FusionMapper, fusion_file: &str) -> Result<(), Error> {
+    let gs = global_settings();
+    let file = |name: &str| report_file_name(name, fusion_file);
+
+    let index_stats = file(&gs.index_stats_file);
+    if !index_stats.is_empty() {
+        write_index_stats_tsv(&index_stats, &mapper.m_indexer.m_index_stats)?;
+    }
+
+    let rejected_reads = file(&gs.rejected_reads_file);
+    if !rejected_reads.is_empty() {
+        write_rejected_reads(&rejected_reads, &mapper.m_rejections.m_reads)?;
+    }
+
+    let consensus = file(&gs.consensus_fasta_file);
+    if !consensus.is_empty() {
+        write_consensus_fasta(&consensus, &mapper.m_fusion_results)?;
+    }
+
+    let tsv = file(&gs.tsv_file);
+    if !tsv.is_empty() {
+        write_fusions_tsv(&tsv, &mapper.m_fusion_results)?;
+    }
+
+    let fusion_fasta = file(&gs.fusion_fasta_file);
+    if !fusion_fasta.is_empty() {
+        write_fusion_fasta(&fusion_fasta, &mapper.m_fusion_results)?;
+    }
+
+    let vcf = file(&gs.vcf_file);
+    if !vcf.is_empty() {
+        let Some(reference) = mapper.m_indexer.get_ref() else {
+            return Err("the reference is needed for the VCF report".into());
+        };
+        write_vcf(&vcf, &mapper.m_fusion_results, reference)?;
+    }
+
+    Ok(())
+}
+
+fn per_csv_file_name(file: &str, fusion_csv: &str) -> String {
+    let path = Path::new(file);
+    let parent = path.parent().unwrap().to_str().unwrap();
+    let stem = path.file_stem().unwrap().to_str().unwrap();
+    let fc_stem = Path::new(fusion_csv).file_stem().unwrap().to_str().unwrap();
+
+    let name = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, fc_stem, ext.to_str().unwrap()),
+        None => format!("{}_{}", stem, fc_stem),
+    };
+
+    [parent, &name]
+        .iter()
+        .collect::<PathBuf>()
+        .into_os_string()
+        .into_string()
+        .unwrap()
+}
+
 struct ScannerFastaReader {
     fasta_reader: Option<Arc<FastaReader>>, // use Option to use mem::take.
 }
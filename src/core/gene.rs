@@ -8,9 +8,9 @@ use super::fusion_scan::Error;
 
 #[derive(Debug, Clone)]
 pub(crate) struct Exon {
-    id: i32,
-    start: i32,
-    end: i32,
+    pub(crate) id: i32,
+    pub(crate) start: i32,
+    pub(crate) end: i32,
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +21,8 @@ pub(crate) struct Gene {
     pub(crate) m_end: i32,
     pub(crate) m_exons: Vec<Exon>,
     pub(crate) m_reversed: bool,
+    // first and last chromosome positions of the coding sequence, stop codon included
+    pub(crate) m_cds: Option<(i32, i32)>,
 }
 impl Gene {
     pub(crate) fn is_reversed(&self) -> bool {
@@ -41,6 +43,11 @@ impl Gene {
         self.m_name != "invalid" && self.m_start != 0 && self.m_end != 0
     }
 
+    /// the name without a transcript, `ALK` of `ALK_NM_004304`
+    pub(crate) fn symbol(&self) -> &str {
+        self.m_name.split_once('_').map_or(self.m_name.as_str(), |(symbol, _)| symbol)
+    }
+
     pub(crate) fn parse(line_str: &str) -> Result<Self, Error> {
         let splitted = line_str.split(",").collect::<Vec<&str>>();
         log::debug!("splitted={:?}", splitted);
@@ -86,7 +93,21 @@ impl Gene {
             // .or_exp()?;
             .or_else(|e| Err(format!("{e:?}, {}", range.get(1).unwrap().trim())))?;
 
-        Ok(Gene::new(name, chr, start, end))
+        let mut gene = Gene::new(name, chr, start, end);
+
+        // an optional coding sequence, e.g. `>EGFR,chr7:55086725-55279321,cds:55086971-55273310`
+        if let Some(cds) = splitted.get(2) {
+            let range = cds
+                .trim()
+                .strip_prefix("cds:")
+                .and_then(|r| r.split_once("-"))
+                .ok_or_else(|| format!("cds should be like cds:start-end, {}", cds.trim()))?;
+            let cds_start = range.0.trim().parse::<i32>().or_else(|e| Err(format!("{e:?}, {}", range.0.trim())))?;
+            let cds_end = range.1.trim().parse::<i32>().or_else(|e| Err(format!("{e:?}, {}", range.1.trim())))?;
+            gene.m_cds = Some((cds_start, cds_end));
+        }
+
+        Ok(gene)
     }
 
     pub(crate) fn add_exon(&mut self, id: i32, start: i32, end: i32) -> () {
@@ -204,6 +225,41 @@ impl Gene {
         }
     }
 
+    /// the position is the first or last base of an exon, give or take `tolerance`
+    pub(crate) fn is_exon_boundary(&self, pos: i32, tolerance: i32) -> bool {
+        let pp = pos.abs() + self.m_start;
+
+        self.m_exons
+            .iter()
+            .any(|e| (pp - e.start).abs() <= tolerance || (pp - e.end).abs() <= tolerance)
+    }
+
+    /// the exon ending at `pos` going along the strand of `pos`, as the left side of a junction does
+    pub(crate) fn exon_ending_at(&self, pos: i32) -> Option<&Exon> {
+        let pp = pos.abs() + self.m_start;
+
+        self.m_exons
+            .iter()
+            .find(|e| if pos >= 0 { e.end == pp } else { e.start == pp })
+    }
+
+    /// the exon starting at `pos` going along the strand of `pos`, as the right side of a junction does
+    pub(crate) fn exon_starting_at(&self, pos: i32) -> Option<&Exon> {
+        let pp = pos.abs() + self.m_start;
+
+        self.m_exons
+            .iter()
+            .find(|e| if pos >= 0 { e.start == pp } else { e.end == pp })
+    }
+
+    pub(crate) fn is_exon_end(&self, pos: i32) -> bool {
+        self.exon_ending_at(pos).is_some()
+    }
+
+    pub(crate) fn is_exon_start(&self, pos: i32) -> bool {
+        self.exon_starting_at(pos).is_some()
+    }
+
     pub(crate) fn gene_pos_2_chr_pos(&self, genepos: i32) -> i32 {
         let mut chrpos = genepos.abs() + self.m_start;
         if genepos < 0 {
@@ -224,6 +280,7 @@ impl Default for Gene {
             m_end: 0,
             m_exons: Default::default(),
             m_reversed: false,
+            m_cds: None,
         }
     }
 }
@@ -0,0 +1,382 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::aux::{global_settings::global_settings, int_hasher::FxHasherBuilder};
+
+use super::{
+    allele_fraction::AlleleFraction, common::GenePos, fusion::Fusion, fusion_scan::Error, gene::Gene,
+    read::SequenceRead, sequence::reverse_complement,
+};
+
+// bases of each gene around a known break used to genotype it
+const HOTSPOT_FLANK: usize = 500;
+
+// a read needs this many bases at each side of the break, far shorter than the mapper needs
+const HOTSPOT_MIN_ANCHOR: usize = 6;
+
+// k-mers placing reads on the sequences around a break
+const HOTSPOT_KMER: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Support {
+    Alt,
+    LeftRef,
+    RightRef,
+}
+
+/// A sequence with a break to be crossed, placing reads by k-mers.
+#[derive(Debug, Default)]
+struct BreakSeq {
+    m_seq: Vec<u8>,
+    // bases before the break
+    m_break: usize,
+    m_kmers: HashMap<u64, Vec<u32>, FxHasherBuilder>,
+}
+
+fn kmers(seq: &[u8]) -> impl Iterator<Item = (usize, u64)> + '_ {
+    seq.windows(HOTSPOT_KMER).enumerate().filter_map(|(i, w)| {
+        w.iter()
+            .try_fold(0_u64, |key, b| {
+                let code = match b {
+                    b'A' => 0,
+                    b'T' => 1,
+                    b'C' => 2,
+                    b'G' => 3,
+                    _ => return None,
+                };
+                Some((key << 2) | code)
+            })
+            .map(|key| (i, key))
+    })
+}
+
+impl BreakSeq {
+    fn new(seq: Vec<u8>, m_break: usize) -> Self {
+        let mut m_kmers: HashMap<u64, Vec<u32>, FxHasherBuilder> = HashMap::with_hasher(FxHasherBuilder::default());
+        for (i, key) in kmers(&seq) {
+            m_kmers.entry(key).or_default().push(i as u32);
+        }
+
+        Self {
+            m_seq: seq,
+            m_break,
+            m_kmers,
+        }
+    }
+
+    /// fewest mismatches of `read` placed inside the sequence with enough bases at both sides of the break
+    fn best_mismatches(&self, read: &[u8]) -> Option<usize> {
+        let mut offsets = kmers(read)
+            .flat_map(|(i, key)| {
+                self.m_kmers
+                    .get(&key)
+                    .into_iter()
+                    .flatten()
+                    .map(move |&pos| pos as i64 - i as i64)
+            })
+            .collect::<Vec<_>>();
+        offsets.sort_unstable();
+        offsets.dedup();
+
+        offsets
+            .into_iter()
+            .filter(|&o| {
+                let before = self.m_break as i64 - o;
+                o >= 0
+                    && o as usize + read.len() <= self.m_seq.len()
+                    && before >= HOTSPOT_MIN_ANCHOR as i64
+                    && read.len() as i64 - before >= HOTSPOT_MIN_ANCHOR as i64
+            })
+            .map(|o| {
+                let o = o as usize;
+                read.iter().zip(self.m_seq[o..(o + read.len())].iter()).filter(|(a, b)| a != b).count()
+            })
+            .min()
+    }
+}
+
+/// A known junction genotyped from every read crossing it, however short its segment at one side.
+///
+/// Reads are placed on the junction and on the wild-type sequences around both breaks, a read
+/// supports whichever of them it matches with the fewest mismatches.
+#[derive(Debug)]
+pub(crate) struct Hotspot {
+    pub(crate) m_left_gene: Gene,
+    pub(crate) m_right_gene: Gene,
+    pub(crate) m_left_gp: GenePos,
+    pub(crate) m_right_gp: GenePos,
+    m_junction: BreakSeq,
+    m_left_wild: BreakSeq,
+    m_right_wild: BreakSeq,
+    m_alt: AtomicUsize,
+    m_left_ref: AtomicUsize,
+    m_right_ref: AtomicUsize,
+}
+
+// the gene sequence in the direction of `position` and the index of `position` in it
+fn stranded(gene_seq: &str, position: i32) -> (Vec<u8>, usize) {
+    if position >= 0 {
+        (gene_seq.as_bytes().to_vec(), position as usize)
+    } else {
+        (
+            reverse_complement(gene_seq).into_bytes(),
+            (position + gene_seq.len() as i32 - 1) as usize,
+        )
+    }
+}
+
+impl Hotspot {
+    /// The left gene reads up to `left_gp`, the right gene from `right_gp`, in the direction of
+    /// their strands. `left_seq` and `right_seq` are the forward gene sequences.
+    pub(crate) fn new(
+        left_gene: Gene,
+        left_gp: GenePos,
+        left_seq: &str,
+        right_gene: Gene,
+        right_gp: GenePos,
+        right_seq: &str,
+    ) -> Self {
+        let (left, i) = stranded(left_seq, left_gp.position);
+        let (right, j) = stranded(right_seq, right_gp.position);
+
+        let left_start = (i + 1).saturating_sub(HOTSPOT_FLANK);
+        let left_end = (i + 1 + HOTSPOT_FLANK).min(left.len());
+        let right_start = j.saturating_sub(HOTSPOT_FLANK);
+        let right_end = (j + HOTSPOT_FLANK).min(right.len());
+
+        let mut junction = left[left_start..=i].to_vec();
+        junction.extend_from_slice(&right[j..right_end]);
+
+        Self {
+            m_left_gene: left_gene,
+            m_right_gene: right_gene,
+            m_left_gp: left_gp,
+            m_right_gp: right_gp,
+            m_junction: BreakSeq::new(junction, i + 1 - left_start),
+            m_left_wild: BreakSeq::new(left[left_start..left_end].to_vec(), i + 1 - left_start),
+            m_right_wild: BreakSeq::new(right[right_start..right_end].to_vec(), j - right_start),
+            m_alt: AtomicUsize::new(0),
+            m_left_ref: AtomicUsize::new(0),
+            m_right_ref: AtomicUsize::new(0),
+        }
+    }
+
+    /// TSV of left_gene, left_position, right_gene, right_position and orientation like `+/-`,
+    /// the positions as in the JSON report and the genes from the fusion file
+    pub(crate) fn read_file(file: &str, fusion_list: &[Fusion], fusion_seq: &[String]) -> Result<Vec<Self>, Error> {
+        let reader = BufReader::new(File::open(file)?);
+        let mut hotspots = Vec::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let at = |msg: String| -> Error { format!("{}:{}: {}", file, i + 1, msg).into() };
+
+            let cols = line.split('\t').map(|c| c.trim()).collect::<Vec<_>>();
+            if cols.len() < 5 {
+                return Err(at(
+                    "a hotspot line needs left_gene, left_position, right_gene, right_position and orientation columns"
+                        .to_owned(),
+                ));
+            }
+
+            let strands = match cols[4] {
+                "+/+" => (1, 1),
+                "+/-" => (1, -1),
+                "-/+" => (-1, 1),
+                "-/-" => (-1, -1),
+                o => return Err(at(format!("orientation '{}' is not one of +/+, +/-, -/+ and -/-", o))),
+            };
+
+            let side = |name: &str, position: &str, strand: i32| -> Result<(usize, GenePos), Error> {
+                let contig = fusion_list
+                    .iter()
+                    .position(|f| f.m_gene.m_name == name)
+                    .ok_or_else(|| at(format!("gene '{}' is not in the fusion file", name)))?;
+                let gene = &fusion_list[contig].m_gene;
+                let pos = position.parse::<i32>()? - gene.m_start;
+                if pos < 0 || pos as usize >= fusion_seq[contig].len() {
+                    return Err(at(format!("position {} is out of gene '{}'", position, name)));
+                }
+
+                Ok((contig, GenePos { contig: contig as i16, position: strand * pos }))
+            };
+
+            let (left, left_gp) = side(cols[0], cols[1], strands.0)?;
+            let (right, right_gp) = side(cols[2], cols[3], strands.1)?;
+
+            hotspots.push(Self::new(
+                fusion_list[left].m_gene.clone(),
+                left_gp,
+                &fusion_seq[left],
+                fusion_list[right].m_gene.clone(),
+                right_gp,
+                &fusion_seq[right],
+            ));
+        }
+
+        Ok(hotspots)
+    }
+
+    /// what the read supports on either strand, `None` if it's not at the breaks or ambiguous
+    pub(crate) fn classify(&self, seq: &str) -> Option<Support> {
+        let max_mismatches = global_settings().diff_threshold as usize - 1;
+        let rc = reverse_complement(seq);
+
+        let hits = [seq.as_bytes(), rc.as_bytes()]
+            .into_iter()
+            .flat_map(|read| {
+                [
+                    (self.m_junction.best_mismatches(read), Support::Alt),
+                    (self.m_left_wild.best_mismatches(read), Support::LeftRef),
+                    (self.m_right_wild.best_mismatches(read), Support::RightRef),
+                ]
+            })
+            .filter_map(|(mismatches, support)| mismatches.filter(|&m| m <= max_mismatches).map(|m| (m, support)))
+            .collect::<Vec<_>>();
+
+        let best = hits.iter().map(|&(m, _)| m).min()?;
+        let mut supports = hits.iter().filter(|&&(m, _)| m == best).map(|&(_, s)| s);
+        let first = supports.next()?;
+
+        supports.all(|s| s == first).then_some(first)
+    }
+
+    /// `reads` of one fragment, counted once, for the fusion if any read supports it
+    pub(crate) fn count_fragment(&self, reads: &[&SequenceRead]) {
+        let supports = reads.iter().filter_map(|r| self.classify(&r.m_seq.m_str)).collect::<Vec<_>>();
+
+        if supports.contains(&Support::Alt) {
+            self.m_alt.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if supports.contains(&Support::LeftRef) {
+            self.m_left_ref.fetch_add(1, Ordering::Relaxed);
+        }
+        if supports.contains(&Support::RightRef) {
+            self.m_right_ref.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn allele_fraction(&self) -> AlleleFraction {
+        AlleleFraction::new(
+            self.m_alt.load(Ordering::Relaxed),
+            self.m_left_ref.load(Ordering::Relaxed),
+            self.m_right_ref.load(Ordering::Relaxed),
+        )
+    }
+
+    /// `./.` without any read at the breaks, `1/1` without reference reads
+    pub(crate) fn genotype(&self) -> &'static str {
+        let af = self.allele_fraction();
+        match (af.m_alt_reads > 0, af.m_left_ref_reads + af.m_right_ref_reads > 0) {
+            (false, false) => "./.",
+            (false, true) => "0/0",
+            (true, true) => "0/1",
+            (true, false) => "1/1",
+        }
+    }
+
+    /// `ext` bases of the junction at each side of the break, lower case after it
+    pub(crate) fn junction_seq(&self, ext: usize) -> String {
+        let seq = &self.m_junction.m_seq;
+        let b = self.m_junction.m_break;
+        let left = String::from_utf8_lossy(&seq[b.saturating_sub(ext)..b]).into_owned();
+        let right = String::from_utf8_lossy(&seq[b..(b + ext).min(seq.len())]).to_lowercase();
+
+        left + &right
+    }
+
+    pub(crate) fn id(&self) -> String {
+        let side = |gene: &Gene, gp: &GenePos| {
+            format!(
+                "{}:{}{}:{}",
+                gene.m_name,
+                if gp.position >= 0 { "+" } else { "-" },
+                gene.m_chr,
+                gene.gene_pos_2_chr_pos(gp.position).abs()
+            )
+        };
+
+        format!(
+            "{}___{}",
+            side(&self.m_left_gene, &self.m_left_gp),
+            side(&self.m_right_gene, &self.m_right_gp)
+        )
+    }
+
+    pub(crate) fn print(&self) {
+        let af = self.allele_fraction();
+        println!(
+            "#Hotspot: {}  (genotype: {}, fusion: {}, reference: {}|{}, vaf: {:.4})",
+            self.id(),
+            self.genotype(),
+            af.m_alt_reads,
+            af.m_left_ref_reads,
+            af.m_right_ref_reads,
+            af.m_vaf
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn random_seq(seed: &mut u64, len: usize) -> String {
+        (0..len)
+            .map(|_| {
+                *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                b"ATCG"[(*seed >> 62) as usize] as char
+            })
+            .collect()
+    }
+
+    fn read(seq: &str) -> SequenceRead {
+        SequenceRead::new("@r".to_owned(), seq.to_owned(), "+".to_owned(), "I".repeat(seq.len()), true)
+    }
+
+    #[test]
+    fn genotype_short_segments() {
+        let mut seed = 7;
+        let a = random_seq(&mut seed, 2000);
+        let b = random_seq(&mut seed, 2000);
+
+        // A forward up to 999, B reversed from its forward base 1500
+        let hotspot = Hotspot::new(
+            Gene::default(),
+            GenePos { contig: 0, position: 999 },
+            &a,
+            Gene::default(),
+            GenePos { contig: 1, position: -1500 },
+            &b,
+        );
+        let b_rc = reverse_complement(&b);
+        let j = (-1500 + b.len() as i32 - 1) as usize;
+
+        // 7 bases of B, too short to be mapped
+        let alt = a[907..1000].to_owned() + &b_rc[j..(j + 7)];
+        assert_eq!(hotspot.classify(&alt), Some(Support::Alt));
+        assert_eq!(hotspot.classify(&reverse_complement(&alt)), Some(Support::Alt));
+        assert_eq!(hotspot.classify(&a[950..1050]), Some(Support::LeftRef));
+        assert_eq!(hotspot.classify(&b_rc[(j - 50)..(j + 50)]), Some(Support::RightRef));
+        // not crossing a break
+        assert_eq!(hotspot.classify(&a[800..900]), None);
+
+        assert_eq!(hotspot.genotype(), "./.");
+        hotspot.count_fragment(&[&read(&a[950..1050]), &read(&alt)]);
+        hotspot.count_fragment(&[&read(&a[950..1050])]);
+        assert_eq!(hotspot.genotype(), "0/1");
+
+        let af = hotspot.allele_fraction();
+        assert_eq!((af.m_alt_reads, af.m_left_ref_reads, af.m_right_ref_reads), (1, 1, 0));
+    }
+}
@@ -6,7 +6,7 @@ use chrono::Local;
 
 use crate::{aux::global_settings::global_settings, genefuse::COMMAND};
 
-use super::{fusion_mapper::FusionMapper, fusion_result::FusionResult, fusion_scan::Error};
+use super::{consensus::JunctionConsensus, event_type::EventType, fusion_mapper::FusionMapper, fusion_result::FusionResult, fusion_scan::Error, multi_fusion_result::MultiFusionResult, rejection::filter_funnel};
 
 pub(crate) const FUSIONSCAN_VER: &str = env!("CARGO_PKG_VERSION");
 
@@ -43,6 +43,16 @@ impl<'f, 's> HtmlReporter<'f, 's>
         self.print_helper()?;
         log::debug!("printing fusions...");
         self.print_fusions()?;
+        log::debug!("printing exon skipping...");
+        self.print_exon_skips()?;
+        log::debug!("printing circRNAs...");
+        self.print_circ_rnas()?;
+        log::debug!("printing hotspots...");
+        self.print_hotspots()?;
+        log::debug!("printing filter funnel...");
+        self.print_filter_funnel()?;
+        log::debug!("printing alignable regions...");
+        self.print_alignable_regions()?;
         log::debug!("printing footer...");
         self.print_footer()?;
 
@@ -77,6 +87,16 @@ impl<'f, 's> HtmlReporter<'f, 's>
                 target='_blank'>GeneFuse</a> <font size='-1'>{}</font></div>",
             FUSIONSCAN_VER
         )?;
+        write!(
+            f,
+            "<div class='tips'>Thresholds: {}</div>",
+            global_settings()
+                .thresholds()
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
 
         Ok(())
     }
@@ -253,6 +273,14 @@ impl<'f, 's> HtmlReporter<'f, 's>
             
         }
 
+        for (i, mfr) in self.m_fusion_mapper.m_multi_fusion_results.iter().enumerate() {
+            write!(
+                f,
+                "<li class='menu_item'><a href='#complex_id_{}'> C{}, {}</a></li>",
+                i + 1, i + 1, mfr.m_title
+            )?;
+        }
+
         write!(f, "</ul></div>")?;
 
         let mut id = 0;
@@ -260,7 +288,7 @@ impl<'f, 's> HtmlReporter<'f, 's>
         log::debug!("found={}, self.m_fusion_mapper.m_fusion_results.len()={}", found, self.m_fusion_mapper.m_fusion_results.len());
 
         for mut fusion in self.m_fusion_mapper.m_fusion_results.iter_mut() {
-            if !global_settings().output_deletions && fusion.is_deletion() {
+            if !global_settings().output_deletions && fusion.is_intra_gene_indel() {
                 continue;
             }
             if fusion.is_left_protein_forward() != fusion.is_right_protein_forward() {
@@ -273,6 +301,90 @@ impl<'f, 's> HtmlReporter<'f, 's>
             Self::print_fusion(id, &mut fusion, f)?;
         }
 
+        for (i, mfr) in self.m_fusion_mapper.m_multi_fusion_results.iter().enumerate() {
+            Self::print_complex_fusion(i as i32 + 1, mfr, f)?;
+        }
+
+        Ok(())
+    }
+
+    fn print_complex_fusion(
+        id: i32,
+        mfr: &MultiFusionResult,
+        f: &mut BufWriter<File>,
+    ) -> Result<(), Error> {
+        write!(f, "<div class='fusion_block'>")?;
+        write!(f, "<div class='fusion_head'><a name='complex_id_{}'>", id)?;
+        write!(f, "C{}, {}", id, mfr.m_title)?;
+        write!(f, "</a></div>")?;
+
+        write!(
+            f,
+            "<div class='tips'>Segments: {}</div>",
+            mfr.m_genes.iter().map(|g| g.m_name.as_str()).collect::<Vec<_>>().join(" → ")
+        )?;
+
+        write!(f, "<div class='tips'>Supporting reads:</div>")?;
+        write!(f, "<table>")?;
+        write!(f, "<tr class='header'>")?;
+        write!(
+            f,
+            "<td class='alignright' colspan='3'>{} = <font color='yellow'>↓</font></td>",
+            mfr.m_left_pos.first().unwrap()
+        )?;
+        for j in 1..mfr.m_junctions.len() {
+            write!(
+                f,
+                "<td><font color='yellow'>↓</font> = {} ... {} = <font color='yellow'>↓</font></td>",
+                mfr.m_right_pos[j - 1], mfr.m_left_pos[j]
+            )?;
+        }
+        write!(
+            f,
+            "<td class='alignleft'><font color='yellow'>↓</font> = {}</td>",
+            mfr.m_right_pos.last().unwrap()
+        )?;
+        write!(f, "</tr>")?;
+
+        let colspan = mfr.segment_count() + 4;
+        for (m, me) in mfr.m_matches.iter().enumerate() {
+            // complex blocks use a different row id range from the fusion blocks
+            let rowid = -(id*100000 + m as i32);
+            write!(f, "<tr onclick='toggle({});'>", rowid)?;
+            write!(f, "<td>")?;
+            write!(f, "<a title='{}'>", me.m_read.m_name)?;
+            write!(f, "{:04}", m+1)?;
+            me.print_html_td(f)?;
+            write!(f, "</tr>")?;
+
+            write!(f, "<tr id='{}' style='display:none;'>", rowid)?;
+            write!(f, "<td colspan='{}'><xmp>", colspan)?;
+            me.print_reads_to_file(f)?;
+            write!(f, "</xmp></td>")?;
+            write!(f, "</tr>")?;
+        }
+        write!(f, "</table></div>")?;
+
+        Ok(())
+    }
+
+    fn print_consensus(cons: &JunctionConsensus, f: &mut BufWriter<File>) -> Result<(), Error> {
+        if cons.is_empty() {
+            return Ok(());
+        }
+
+        write!(
+            f,
+            "<div class='tips'>Junction consensus ({} bp, depth {}~{}), move mouse over a base to see its depth, support and quality:</div>",
+            cons.m_seq.len(),
+            cons.m_depth.iter().min().unwrap(),
+            cons.m_depth.iter().max().unwrap()
+        )?;
+        write!(f, "<table><tr>")?;
+        write!(f, "<td class='alignright'>{}</td>", cons.html_seq(0, cons.m_break))?;
+        write!(f, "<td class='alignleft'>{}</td>", cons.html_seq(cons.m_break, cons.m_seq.len()))?;
+        write!(f, "</tr></table>")?;
+
         Ok(())
     }
 
@@ -303,6 +415,138 @@ impl<'f, 's> HtmlReporter<'f, 's>
 
         fusion.print_fusion_protein_html(f)?;
 
+        let frame = &fusion.m_reading_frame;
+        write!(f, "<div class='tips'>Reading frame: {}", frame.m_type.name())?;
+        if frame.protein_length() > 0 {
+            write!(f, ", fusion protein of {} aa", frame.protein_length())?;
+        }
+        if let Some(stop) = frame.m_premature_stop {
+            write!(f, ", premature stop codon at aa {}", stop)?;
+        }
+        write!(f, "</div>")?;
+
+        if !fusion.m_domains.is_empty() {
+            let domains = fusion.m_domains.iter().map(|d| format!("{} ({})", d.description(), d.partner())).collect::<Vec<_>>();
+            write!(f, "<div class='tips'>Protein domains: {}</div>", domains.join(", "))?;
+        }
+
+        write!(f, "<div class='tips'>Confidence score: {:.3} (", fusion.m_score)?;
+        let components = fusion.m_confidence.components(global_settings().diff_threshold);
+        for (i, (name, value)) in components.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {:.2}", name, value)?;
+        }
+        write!(f, ")</div>")?;
+
+        if fusion.m_event_type != EventType::Fusion {
+            write!(
+                f,
+                "<div class='tips'>Event: {} of {} bp on {}</div>",
+                fusion.m_event_type.title().to_lowercase(),
+                fusion.m_event_size,
+                fusion.m_left_gene.m_chr
+            )?;
+        }
+
+        let bc = &fusion.m_break_context;
+        write!(
+            f,
+            "<div class='tips'>Breakpoint context: splice motif {} ({})",
+            bc.splice_motif(),
+            bc.splice_class()
+        )?;
+        if bc.microhomology_len() > 0 {
+            let ((left_first, left_last), (right_first, right_last)) = fusion.break_ranges();
+            write!(
+                f,
+                ", microhomology {} ({} bp), breaks possible at {}~{} | {}~{}",
+                bc.m_microhomology,
+                bc.microhomology_len(),
+                left_first,
+                left_last,
+                right_first,
+                right_last
+            )?;
+        } else {
+            write!(f, ", no microhomology")?;
+        }
+        write!(f, "</div>")?;
+
+        let af = &fusion.m_allele_fraction;
+        write!(
+            f,
+            "<div class='tips'>Allele fraction: {:.3} (95% CI {:.3}~{:.3}), {} fusion reads, {} | {} reference reads at left | right break</div>",
+            af.m_vaf, af.m_ci_low, af.m_ci_high, af.m_alt_reads, af.m_left_ref_reads, af.m_right_ref_reads
+        )?;
+
+        if global_settings().rna_mode {
+            write!(
+                f,
+                "<div class='tips'>Junction: {}, {} of 2 breaks at exon boundaries",
+                if fusion.is_canonical() { "canonical" } else { "non-canonical" },
+                fusion.m_exon_boundary_sides
+            )?;
+            if fusion.m_snap_shift != 0 {
+                write!(f, ", moved by {} bases along identical sequence to reach them", fusion.m_snap_shift)?;
+            }
+            write!(f, "</div>")?;
+        }
+
+        if let Some(gap) = fusion.m_read_through {
+            write!(
+                f,
+                "<div class='tips'>Read-through candidate: {} is the next gene downstream of {} on the same strand, {} bp apart, transcription running on past the end of {} would join them</div>",
+                fusion.three_prime_gene().m_name,
+                fusion.five_prime_gene().m_name,
+                gap,
+                fusion.five_prime_gene().m_name
+            )?;
+        }
+
+        if !global_settings().known_fusions_file.is_empty() {
+            let known = &fusion.m_known;
+            write!(f, "<div class='tips'>Known fusions: {}", known.m_status.title())?;
+            if !known.m_sources.is_empty() {
+                write!(f, " ({})", known.m_sources.join(", "))?;
+            }
+            write!(f, "</div>")?;
+        }
+
+        if let Some(hit) = &fusion.m_pon {
+            write!(
+                f,
+                "<div class='tips'>Panel of normals: found in {} of {} normal samples (frequency {:.3}), likely an artefact of the assay</div>",
+                hit.m_samples,
+                hit.m_total_samples,
+                hit.frequency()
+            )?;
+        }
+
+        Self::print_consensus(&fusion.m_consensus, f)?;
+
+        if let Some(rp) = &fusion.m_reciprocal {
+            write!(
+                f,
+                "<div class='tips'>Reciprocal event E{}: {} (unique:{}, total:{}){}</div>",
+                fusion.m_event_id,
+                rp.m_id,
+                rp.m_unique,
+                rp.m_total,
+                if rp.m_collapsed { ", collapsed into this fusion" } else { "" }
+            )?;
+        }
+
+        if !fusion.m_spanning_pairs.is_empty() {
+            write!(
+                f,
+                "<div class='tips'>Spanning read pairs: {} unique, {} total</div>",
+                fusion.spanning_unique(),
+                fusion.m_spanning_pairs.len()
+            )?;
+        }
+
         write!(f, "<div class='tips'>Supporting reads:</div>")?;
         write!(f, "<table>")?;
         write!(f, "<tr class='header'>")?;
@@ -363,6 +607,163 @@ impl<'f, 's> HtmlReporter<'f, 's>
         Ok(())
     }
 
+    fn print_exon_skips(&mut self) -> Result<(), Error> {
+        let skips = &self.m_fusion_mapper.m_exon_skips;
+        if skips.is_empty() {
+            return Ok(());
+        }
+
+        let f = &mut self.m_file;
+        write!(f, "<div class='fusion_block'>")?;
+        write!(f, "<div class='fusion_head'>Exon skipping</div>")?;
+        write!(f, "<table>")?;
+        write!(
+            f,
+            "<tr class='header'><td>event</td><td>junction</td><td>unique reads</td><td>total reads</td><td>normal junction reads (upstream | downstream exon)</td></tr>"
+        )?;
+        for es in skips.iter() {
+            write!(
+                f,
+                "<tr><td>{}</td><td>{}:{} - {}:{}</td><td>{}</td><td>{}</td><td>{} | {}</td></tr>",
+                es.title(),
+                es.m_chr,
+                es.m_left_pos,
+                es.m_chr,
+                es.m_right_pos,
+                es.m_unique,
+                es.m_reads,
+                es.m_left_normal_reads,
+                es.m_right_normal_reads
+            )?;
+        }
+        write!(f, "</table></div>")?;
+
+        Ok(())
+    }
+
+    fn print_circ_rnas(&mut self) -> Result<(), Error> {
+        if !global_settings().circrna_mode {
+            return Ok(());
+        }
+
+        let circs = &self.m_fusion_mapper.m_circ_rnas;
+        let f = &mut self.m_file;
+        write!(f, "<div class='fusion_block'>")?;
+        write!(f, "<div class='fusion_head'>Circular RNAs ({} found)</div>", circs.len())?;
+        write!(f, "<table>")?;
+        write!(
+            f,
+            "<tr class='header'><td>circRNA</td><td>back-splice junction</td><td>unique reads</td><td>total reads</td><td>linear reads (acceptor | donor)</td><td>circular/linear</td></tr>"
+        )?;
+        for c in circs.iter() {
+            write!(
+                f,
+                "<tr><td>{}</td><td>{}:{} - {}:{}</td><td>{}</td><td>{}</td><td>{} | {}</td><td>{}</td></tr>",
+                c.title(),
+                c.m_chr,
+                c.m_donor_pos,
+                c.m_chr,
+                c.m_acceptor_pos,
+                c.m_unique,
+                c.m_reads,
+                c.m_acceptor_linear_reads,
+                c.m_donor_linear_reads,
+                c.ratio_str()
+            )?;
+        }
+        write!(f, "</table></div>")?;
+
+        Ok(())
+    }
+
+    fn print_hotspots(&mut self) -> Result<(), Error> {
+        let hotspots = &self.m_fusion_mapper.m_hotspots;
+        if hotspots.is_empty() {
+            return Ok(());
+        }
+
+        let f = &mut self.m_file;
+        write!(f, "<div class='fusion_block'>")?;
+        write!(f, "<div class='fusion_head'>Hotspot genotypes</div>")?;
+        write!(f, "<table>")?;
+        write!(
+            f,
+            "<tr class='header'><td>junction</td><td>sequence</td><td>genotype</td><td>fusion reads</td><td>reference reads (left | right)</td><td>VAF (95% CI)</td></tr>"
+        )?;
+        for hotspot in hotspots.iter() {
+            let af = hotspot.allele_fraction();
+            write!(
+                f,
+                "<tr><td>{}</td><td class='alignleft'>{}</td><td>{}</td><td>{}</td><td>{} | {}</td><td>{:.3} ({:.3}~{:.3})</td></tr>",
+                hotspot.id(),
+                hotspot.junction_seq(20),
+                hotspot.genotype(),
+                af.m_alt_reads,
+                af.m_left_ref_reads,
+                af.m_right_ref_reads,
+                af.m_vaf,
+                af.m_ci_low,
+                af.m_ci_high
+            )?;
+        }
+        write!(f, "</table></div>")?;
+
+        Ok(())
+    }
+
+    fn print_filter_funnel(&mut self) -> Result<(), Error> {
+        let funnel = filter_funnel(
+            self.m_fusion_mapper.m_candidate_count,
+            &self.m_fusion_mapper.m_rejections,
+        );
+
+        let f = &mut self.m_file;
+        write!(f, "<div class='fusion_block'>")?;
+        write!(
+            f,
+            "<div class='fusion_head'>Candidate reads through filters ({} before filtering)</div>",
+            self.m_fusion_mapper.m_candidate_count
+        )?;
+        write!(f, "<table>")?;
+        write!(f, "<tr class='header'><td>stage</td><td>removed</td><td>remaining</td></tr>")?;
+        for row in funnel.iter() {
+            write!(
+                f,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                row.stage, row.removed, row.remaining
+            )?;
+        }
+        write!(f, "</table></div>")?;
+
+        Ok(())
+    }
+
+    fn print_alignable_regions(&mut self) -> Result<(), Error> {
+        let regions = &self.m_fusion_mapper.m_alignable_regions;
+        if regions.is_empty() {
+            return Ok(());
+        }
+
+        let f = &mut self.m_file;
+        write!(f, "<div class='fusion_block'>")?;
+        write!(f, "<div class='fusion_head'>Removed reads aligned contiguously to the reference</div>")?;
+        write!(f, "<table>")?;
+        write!(f, "<tr class='header'><td>region</td><td>reads</td></tr>")?;
+        for ar in regions.iter() {
+            write!(
+                f,
+                "<tr><td>{}:{}-{}</td><td>{}</td></tr>",
+                ar.contig,
+                ar.start + 1,
+                ar.end,
+                ar.reads
+            )?;
+        }
+        write!(f, "</table></div>")?;
+
+        Ok(())
+    }
+
     fn print_scan_targets(&mut self) -> Result<(), Error> {
         // original cpp code does nothing.
         Ok(())
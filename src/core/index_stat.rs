@@ -0,0 +1,167 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use crate::aux::global_settings::global_settings;
+
+use super::{
+    common::{GenePos, DUPE_HIGH_LEVEL, DUPE_NORMAL_LEVEL},
+    fusion_scan::Error,
+};
+
+/// Indexed k-mers of a region, counted by how the mapper can use them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct KmerUniqueness {
+    pub(crate) unique: usize,
+    // k-mers shared with a few other places, their votes are split
+    pub(crate) normal_dupe: usize,
+    // k-mers skipped by the mapper, seen more than skip_key_dup_threshold times
+    pub(crate) high_dupe: usize,
+}
+
+impl KmerUniqueness {
+    pub(crate) fn add(&mut self, gp: &GenePos) {
+        match gp.contig {
+            DUPE_HIGH_LEVEL => self.high_dupe += 1,
+            DUPE_NORMAL_LEVEL => self.normal_dupe += 1,
+            _ => self.unique += 1,
+        }
+    }
+
+    pub(crate) fn total(&self) -> usize {
+        self.unique + self.normal_dupe + self.high_dupe
+    }
+
+    pub(crate) fn unique_ratio(&self) -> f64 {
+        if self.total() == 0 {
+            return 0.0;
+        }
+
+        self.unique as f64 / self.total() as f64
+    }
+
+    /// too few unique k-mers, reads in this region are mostly voted to its paralogs
+    pub(crate) fn is_paralog_affected(&self) -> bool {
+        self.unique_ratio() < global_settings().paralog_unique_ratio
+    }
+
+    pub(crate) fn flag(&self) -> &'static str {
+        if self.total() == 0 {
+            "NOT_INDEXED"
+        } else if self.is_paralog_affected() {
+            "PARALOG"
+        } else {
+            "PASS"
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ExonIndexStat {
+    pub(crate) id: i32,
+    pub(crate) start: i32,
+    pub(crate) end: i32,
+    pub(crate) kmers: KmerUniqueness,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct GeneIndexStat {
+    pub(crate) name: String,
+    pub(crate) chr: String,
+    pub(crate) start: i32,
+    pub(crate) end: i32,
+    pub(crate) kmers: KmerUniqueness,
+    pub(crate) exons: Vec<ExonIndexStat>,
+}
+
+impl GeneIndexStat {
+    pub(crate) fn is_paralog_affected(&self) -> bool {
+        self.kmers.total() == 0 || self.kmers.is_paralog_affected()
+    }
+
+    pub(crate) fn flag(&self) -> &'static str {
+        self.kmers.flag()
+    }
+}
+
+pub(crate) fn write_index_stats_tsv(file: &str, stats: &[GeneIndexStat]) -> Result<(), Error> {
+    let mut f = BufWriter::new(File::create(file)?);
+
+    writeln!(
+        f,
+        "gene\tregion\tchr\tstart\tend\tkmers\tunique\tnormal_dupe\thigh_dupe\tunique_ratio\tflag"
+    )?;
+
+    for gs in stats.iter() {
+        write_tsv_line(&mut f, &gs.name, "gene", &gs.chr, gs.start, gs.end, &gs.kmers)?;
+
+        for es in gs.exons.iter() {
+            write_tsv_line(
+                &mut f,
+                &gs.name,
+                &format!("exon:{}", es.id),
+                &gs.chr,
+                es.start,
+                es.end,
+                &es.kmers,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_tsv_line(
+    f: &mut BufWriter<File>,
+    gene: &str,
+    region: &str,
+    chr: &str,
+    start: i32,
+    end: i32,
+    kmers: &KmerUniqueness,
+) -> Result<(), Error> {
+    writeln!(
+        f,
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:.4}\t{}",
+        gene,
+        region,
+        chr,
+        start,
+        end,
+        kmers.total(),
+        kmers.unique,
+        kmers.normal_dupe,
+        kmers.high_dupe,
+        kmers.unique_ratio(),
+        kmers.flag()
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn uniqueness_flag() {
+        let mut kmers = KmerUniqueness::default();
+        assert_eq!(kmers.flag(), "NOT_INDEXED");
+
+        for contig in [0, 3, DUPE_NORMAL_LEVEL, DUPE_HIGH_LEVEL, DUPE_HIGH_LEVEL] {
+            kmers.add(&GenePos {
+                contig,
+                position: 0,
+            });
+        }
+
+        assert_eq!(kmers.total(), 5);
+        assert_eq!(kmers.unique_ratio(), 0.4);
+        // default paralog_unique_ratio is 0.5
+        assert_eq!(kmers.flag(), "PARALOG");
+
+        kmers.unique += 5;
+        assert_eq!(kmers.flag(), "PASS");
+    }
+}
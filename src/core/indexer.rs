@@ -24,11 +24,12 @@ use crate::{
 };
 
 use super::{
-    common::GenePos, fasta_reader::FastaReader, fastq_reader::FastqReader, fusion::Fusion, fusion_scan::Error, gene::Gene, read::SequenceRead, sequence::{reverse_complement, Sequence}
+    common::GenePos, index_stat::{ExonIndexStat, GeneIndexStat, KmerUniqueness}, kmer_vote::{encode_kmers, KmerVoteTable, MapReadBuffers, MAP_READ_BUFFERS}, fasta_reader::FastaReader, fastq_reader::FastqReader, fusion::Fusion, fusion_scan::Error, gene::Gene, read::SequenceRead, sequence::{reverse_complement, Sequence}
 };
 
-const MATCH_TOP: u8 = 3;
-const MATCH_SECOND: u8 = 2;
+const MATCH_TOP: u8 = 4;
+const MATCH_SECOND: u8 = 3;
+const MATCH_THIRD: u8 = 2;
 const MATCH_NONE: u8 = 1;
 const MATCH_UNKNOWN: u8 = 0;
 
@@ -75,6 +76,7 @@ pub(crate) struct Indexer {
     pub(crate) m_bloom_filter: Box<[u8]>, // `u8` in rust corresponds to unsigned char in C
     pub(crate) m_dupe_list: Vec<Vec<GenePos>>,
     pub(crate) m_fusion_seq: Vec<String>,
+    pub(crate) m_index_stats: Vec<GeneIndexStat>,
 }
 
 impl Indexer {
@@ -94,6 +96,7 @@ impl Indexer {
             m_bloom_filter: vec![0; BLOOM_FILTER_SIZE].into_boxed_slice(),
             m_dupe_list: Vec::new(),
             m_fusion_seq: Vec::new(),
+            m_index_stats: Vec::new(),
         })
     }
 
@@ -108,6 +111,7 @@ impl Indexer {
             m_bloom_filter: vec![0; BLOOM_FILTER_SIZE].into_boxed_slice(),
             m_dupe_list: Vec::new(),
             m_fusion_seq: Vec::new(),
+            m_index_stats: Vec::new(),
         }
     }
 
@@ -174,6 +178,69 @@ impl Indexer {
 
         self.fill_bloom_filter();
         log::info!("mapper indexing done.");
+
+        self.print_stat();
+        self.m_index_stats = self.calc_index_stats();
+    }
+
+    /// count unique and duplicated k-mers of every gene and exon, on the forward strand
+    fn calc_index_stats(&self) -> Vec<GeneIndexStat> {
+        let mut kmers = Vec::new();
+
+        self.m_fusions
+            .iter()
+            .zip(self.m_fusion_seq.iter())
+            .map(|(fusion, seq)| {
+                let gene = &fusion.m_gene;
+                let mut gene_stat = GeneIndexStat {
+                    name: gene.m_name.clone(),
+                    chr: gene.m_chr.clone(),
+                    start: gene.m_start,
+                    end: gene.m_end,
+                    kmers: KmerUniqueness::default(),
+                    exons: gene
+                        .m_exons
+                        .iter()
+                        .map(|e| ExonIndexStat {
+                            id: e.id,
+                            start: e.start,
+                            end: e.end,
+                            kmers: KmerUniqueness::default(),
+                        })
+                        .collect(),
+                };
+
+                encode_kmers(seq.as_bytes(), &mut kmers);
+                // same range as index_contig()
+                let indexed = (seq.len() as i32 - KMER).max(0) as usize;
+                for (i, kmer) in kmers.iter().enumerate().take(indexed) {
+                    let Some(gp) = self.m_kmer_pos.get(kmer) else {
+                        continue;
+                    };
+
+                    gene_stat.kmers.add(gp);
+
+                    let chr_pos = gene.m_start + i as i32;
+                    if let Some(es) = gene_stat
+                        .exons
+                        .iter_mut()
+                        .find(|es| es.start <= chr_pos && chr_pos <= es.end)
+                    {
+                        es.kmers.add(gp);
+                    }
+                }
+
+                if gene_stat.is_paralog_affected() {
+                    log::warn!(
+                        "{}: only {:.1}% of k-mers are unique, breakpoints in it may be undetectable because of paralogs.",
+                        gene_stat.name,
+                        gene_stat.kmers.unique_ratio() * 100.0
+                    );
+                }
+
+                gene_stat
+            })
+            .collect()
     }
 
     fn index_contig(&mut self, ctg: usize, seq: &str, start: i32) {
@@ -249,74 +316,123 @@ impl Indexer {
         }
     }
 
+    /// how uniquely the k-mers of `seq` are indexed, a k-mer with n copies counts 1/n and one
+    /// with too many copies 0. k-mers not in the index are not counted.
+    pub(crate) fn kmer_unique_ratio(&self, seq: &str) -> f64 {
+        let mut kmers = Vec::new();
+        encode_kmers(seq.as_bytes(), &mut kmers);
+
+        let (mut total, mut unique) = (0, 0.0);
+        for kmer in kmers.iter().filter(|k| **k >= 0) {
+            let Some(gp) = self.m_kmer_pos.get(kmer) else {
+                continue;
+            };
+
+            total += 1;
+            unique += match gp.contig {
+                DUPE_HIGH_LEVEL => 0.0,
+                DUPE_NORMAL_LEVEL => 1.0 / self.m_dupe_list[gp.position as usize].len().max(1) as f64,
+                _ => 1.0,
+            };
+        }
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        unique / total as f64
+    }
+
+    /// segments of the read in gene order, a single one when the read lies in one gene only
     pub(crate) fn map_read(&self, r: &SequenceRead) -> Vec<SeqMatch> {
-        // log::debug!("m_kmer_pos={:#?}", self.m_kmer_pos);
+        MAP_READ_BUFFERS.with(|buffers| self.map_read_with(r, &mut buffers.borrow_mut()))
+    }
 
-        // let mut kmer_stat: HashMap<i64, i32, GFHasherBuilder> =
-        //     HashMap::with_hasher(GFHasherBuilder::default());
+    /// gene positions of a k-mer, empty if it is not indexed or has too many copies
+    #[inline]
+    fn kmer_positions(&self, kmer: i64) -> &[GenePos] {
+        let pos = kmer.wrapping_shr(3);
+        let bit = kmer & 0x07;
+        if self.m_bloom_filter[pos as usize] & 0x1_u8.wrapping_shl(bit as u32) == 0 {
+            return &[];
+        }
 
-        let mut kmer_stat: BTreeMap<i64, i32> = BTreeMap::new();
-        kmer_stat.insert(0, 0);
+        let gp = self.m_kmer_pos.get(&kmer).unwrap();
+        if gp.contig == DUPE_HIGH_LEVEL {
+            // too much keys in this dupe, then skip it
+            &[]
+        } else if gp.contig == DUPE_NORMAL_LEVEL {
+            self.m_dupe_list[gp.position as usize].as_slice()
+        } else {
+            std::slice::from_ref(gp)
+        }
+    }
+
+    fn map_read_with(&self, r: &SequenceRead, buffers: &mut MapReadBuffers) -> Vec<SeqMatch> {
+        let MapReadBuffers { kmers, votes, mask } = buffers;
 
-        let seq = r.m_seq.m_str.as_str();
         const step: usize = 2_usize;
-        let seq_cv = seq.chars().collect::<Vec<char>>();
-        let seqlen = seq_cv.len() as i32;
+        let seqlen = r.m_seq.m_str.len() as i32;
+        encode_kmers(r.m_seq.m_str.as_bytes(), kmers);
 
-        // let mut ocw = if r.m_name.contains(DBT) {
-        //     let ocw = ObjectTsvWriter::default();
+        // first pass, we only want to find if this seq can be partially aligned to the target
+        votes.clear();
+        for (i, kmer) in kmers.iter().enumerate().step_by(step) {
+            if *kmer < 0 {
+                continue;
+            }
 
-        //     Some(ocw)
-        // } else {
-        //     None
-        // };
+            for gp in self.kmer_positions(*kmer) {
+                let gplong = gp_to_i64(&shift(gp, i as i32));
+                if gplong != 0 {
+                    votes.vote(gplong);
+                }
+            }
+        }
+
+        // get 1st and 2nd hit
+        //TODO: handle small difference caused by INDEL
+        let ((gp1, count1), (gp2, count2)) = votes.top2();
+
+        if (count1 * step as i32) < global_settings().major_gene_key_requirement {
+            // return an null list
+            return Vec::new();
+        }
+
+        if (count2 * step as i32) < global_settings().minor_gene_key_requirement {
+            // the read lies in one gene only, it can still be a mate of a spanning pair
+            return vec![SeqMatch::new(0, seqlen - 1, i64_to_gp(gp1))];
+        }
+
+        // a third partner makes the read a chain of segments, like a templated insertion
+        let gp3 = third_hit(votes, gp1, gp2, step as i32);
+
+        self.segments(kmers, mask, seqlen, gp1, gp2, gp3)
+    }
+
+    /// `map_read()` with the voting it replaced, on the chars of the read and a `BTreeMap`.
+    /// It is kept for benches/my_benchmark.rs to compare with.
+    #[doc(hidden)]
+    pub(crate) fn map_read_legacy(&self, r: &SequenceRead) -> Vec<SeqMatch> {
+        let mut kmer_stat: BTreeMap<i64, i32> = BTreeMap::new();
+
+        const step: usize = 2_usize;
+        let seq_cv = r.m_seq.m_str.chars().collect::<Vec<char>>();
+        let seqlen = seq_cv.len() as i32;
 
         // first pass, we only want to find if this seq can be partially aligned to the target
         let mut kmer = -1_i64;
-        // log::debug!("map_read() -> seqlen={}", seqlen);
-        for i in (0..(seqlen as i32 - KMER + 1)).step_by(step) {
+        for i in (0..(seqlen - KMER + 1)).step_by(step) {
             kmer = make_kmer_cv(&seq_cv, i, kmer, step as i32);
-            // log::debug!("kmer={}, i={}", kmer, i);
-
-            // ocw.as_mut().and_then(|mut f| {
-            //     write_tsv_row!(&mut f, i,kmer, "1st-pass");
-            //     Some(())
-            // });
-
             if kmer < 0 {
                 continue;
             }
 
-            let pos = kmer.wrapping_shr(3);
-            let bit = kmer & 0x07;
-            if self.m_bloom_filter.get(pos as usize).unwrap()
-                & (0x1_i64.wrapping_shl(bit as u32) as u8)
-                == 0
-            {
-                *kmer_stat.get_mut(&0).unwrap() += 1;
-                continue;
-            }
-
-            let gp = self.m_kmer_pos.get(&kmer).unwrap();
-            // ocw.as_mut().and_then(|mut f| {
-            //     write_tsv_row!(&mut f, i, gp.contig, "1st-pass");
-            //     Some(())
-            // });
-            if gp.contig == DUPE_HIGH_LEVEL {
-                // too much keys in this dupe, then skip it
-                continue;
-            } else if gp.contig == DUPE_NORMAL_LEVEL {
-                let dupe_at_gp_pos = self.m_dupe_list.get(gp.position as usize).unwrap();
-                for g in (0..dupe_at_gp_pos.len()) {
-                    let gplong = gp_to_i64(&shift(dupe_at_gp_pos.get(g).unwrap(), i as i32));
-                    // log::debug!("gplong={} else if", gplong);
+            for gp in self.kmer_positions(kmer) {
+                let gplong = gp_to_i64(&shift(gp, i));
+                if gplong != 0 {
                     kmer_stat.entry(gplong).and_modify(|s| *s += 1).or_insert(1);
                 }
-            } else {
-                // not a dupe
-                let gplong = gp_to_i64(&shift(gp, i as i32));
-                // log::debug!("gplong={} else", gplong);
-                kmer_stat.entry(gplong).and_modify(|s| *s += 1).or_insert(1);
             }
         }
 
@@ -325,216 +441,127 @@ impl Indexer {
         let mut count1 = 0;
         let mut gp2 = 0_i64;
         let mut count2 = 0;
-
-        // log::debug!("kmer_stat={:#?}", kmer_stat);
-        //TODO: handle small difference caused by INDEL
-        // ocw.as_mut().and_then(|mut f| {
-        //     writeln!(&mut f, "kmer_stat={:?}", kmer_stat).unwrap();
-        //     Some(())
-        // });
-
         for (k, v) in kmer_stat.iter() {
-            if *k != 0 && *v > count1 {
+            if *v > count1 {
                 gp2 = gp1;
                 count2 = count1;
                 gp1 = *k;
                 count1 = *v;
-            } else if *k != 0 && *v > count2 {
+            } else if *v > count2 {
                 gp2 = *k;
                 count2 = *v;
             }
         }
 
-        // ocw.as_mut().and_then(|mut f| {
-        //     writeln!(&mut f, "gp1={}, gp2={}, count1={}, count2={}", gp1, gp2, count1, count2).unwrap();
-        //     Some(())
-        // });
-
-        if (count1 * step as i32) < global_settings().major_gene_key_requirement
-            || (count2 * step as i32) < global_settings().minor_gene_key_requirement
-        {
-            // log::debug!("map_read, return empty vec. {}:{}", file!(), line!());
-            // return an null list
-
+        if (count1 * step as i32) < global_settings().major_gene_key_requirement {
             return Vec::new();
         }
 
-        let mut mask: Vec<u8> = vec![MATCH_UNKNOWN; seqlen as usize];
-
-        // second pass, make the mask
-        kmer = -1;
-        for i in (0..(seqlen as i32 - KMER + 1)) {
-            // ocw.as_mut().and_then(|mut f| {
-            //     write!(&mut f, "\"{}\",{},\"", "sp",i).unwrap();
-            //     write!(&mut f, "\"\n", ).unwrap();
-            //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-            //     Some(())
-            // });
+        if (count2 * step as i32) < global_settings().minor_gene_key_requirement {
+            return vec![SeqMatch::new(0, seqlen - 1, i64_to_gp(gp1))];
+        }
 
-            kmer = make_kmer_cv(&seq_cv, i, kmer, 1);
-            // ocw.as_mut().and_then(|mut f| {
-            //     write_tsv_row!(&mut f, i,kmer, "2nd-pass");
-            //     Some(())
-            // });
-            if kmer < 0 {
-                // ocw.as_mut().and_then(|mut f| {
-                //     write!(&mut f, "\"k{}\",{},\"", kmer,i).unwrap();
-                //     write!(&mut f, "\"\n", ).unwrap();
-                //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                //     Some(())
-                // });
+        let mut gp3 = 0_i64;
+        let mut count3 = 0;
+        for (k, v) in kmer_stat.iter() {
+            if *k == gp1 || *k == gp2 || near_hit(*k, gp1) || near_hit(*k, gp2) {
                 continue;
             }
-            let pos = kmer.wrapping_shr(3);
-            let bit = kmer & 0x07;
 
-            // ocw.as_mut().and_then(|mut f| {
-            //     log::debug!("i={}, bloom_filter_pos={}, bit={}", i, self.m_bloom_filter.get(pos as usize).unwrap(), 0x1_u8.wrapping_shl(bit as u32));
-            //     Some(())
-            // });
+            if *v > count3 {
+                gp3 = *k;
+                count3 = *v;
+            }
+        }
+        let gp3 = (count3 * step as i32 >= global_settings().minor_gene_key_requirement).then_some(gp3);
 
-            if self.m_bloom_filter.get(pos as usize).unwrap()
-                & (0x1_u8.wrapping_shl(bit as u32) as u8)
-                == 0
-            {
-                // ocw.as_mut().and_then(|mut f| {
-                //     let c = format!("{}, {}", self.m_bloom_filter.get(pos as usize).unwrap(), (0x1_u8.wrapping_shl(bit as u32) as u8));
-                //     write!(&mut f, "\"b{}\",{},\"", c,i).unwrap();
-                //     write!(&mut f, "\"\n", ).unwrap();
-                //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                //     Some(())
-                // });
+        // second pass, a k-mer at every position
+        let mut kmers = Vec::with_capacity(seq_cv.len());
+        let mut kmer = -1_i64;
+        for i in 0..(seqlen - KMER + 1) {
+            kmer = make_kmer_cv(&seq_cv, i, kmer, 1);
+            kmers.push(kmer);
+        }
+
+        self.segments(&kmers, &mut Vec::new(), seqlen, gp1, gp2, gp3)
+    }
+
+    /// cut the read into the segments of `gp1`, `gp2` and `gp3` by masking each base with the
+    /// best voted position of the k-mers covering it
+    fn segments(
+        &self,
+        kmers: &[i64],
+        mask: &mut Vec<u8>,
+        seqlen: i32,
+        gp1: i64,
+        gp2: i64,
+        gp3: Option<i64>,
+    ) -> Vec<SeqMatch> {
+        mask.clear();
+        mask.resize(seqlen as usize, MATCH_UNKNOWN);
+
+        // second pass, make the mask
+        for (i, kmer) in kmers.iter().enumerate() {
+            if *kmer < 0 {
                 continue;
             }
 
-            let gp = self.m_kmer_pos.get(&kmer).unwrap();
-            // ocw.as_mut().and_then(|mut f| {
-            //     write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-            //     Some(())
-            // });
-            // is a dupe
-            if gp.contig == DUPE_HIGH_LEVEL {
-                // ocw.as_mut().and_then(|mut f| {
-                //     write!(&mut f, "\"c{}\",{},\"", gp.contig,i).unwrap();
-                //     write!(&mut f, "\"\n", ).unwrap();
-                //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                //     Some(())
-                // });
-                // too much keys in this dupe, then skip it
-                continue;
-            } else if gp.contig == DUPE_NORMAL_LEVEL {
-                for g in (0..self.m_dupe_list.get(gp.position as usize).unwrap().len()) {
-                    let gplong = gp_to_i64(&shift(
-                        self.m_dupe_list
-                            .get(gp.position as usize)
-                            .unwrap()
-                            .get(g)
-                            .unwrap(),
-                        i as i32,
-                    ));
-
-                    // ocw.as_mut().and_then(|mut f| {
-                    //     write!(&mut f, "{},{},\"", g,i).unwrap();
-                    //     write!(&mut f, "gplong={}\"\n", gplong).unwrap();
-                    //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                    //     Some(())
-                    // });
-
-                    if (gplong - gp1).abs() <= 1 {
-                        make_mask(mask.as_mut_slice(), MATCH_TOP, seqlen, i, KMER);
-                        // ocw.as_mut().and_then(|mut f| {
-                        //     write!(&mut f, "{},{},\"", g,i).unwrap();
-                        //     for m in mask.iter() {
-                        //         write!(&mut f, "{}", m).unwrap();
-                        //     }
-                        //     write!(&mut f, "\"\n", ).unwrap();
-                        //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                        //     Some(())
-                        // });
-                    } else if (gplong - gp2).abs() <= 1 {
-                        make_mask(mask.as_mut_slice(), MATCH_SECOND, seqlen, i, KMER);
-                        // ocw.as_mut().and_then(|mut f| {
-                        //     write!(&mut f, "{},{},\"", g,i).unwrap();
-                        //     for m in mask.iter() {
-                        //         write!(&mut f, "{}", m).unwrap();
-                        //     }
-                        //     write!(&mut f, "\"\n", ).unwrap();
-                        //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                        //     Some(())
-                        // });
-                    } else if (gplong == 0) {
-                        make_mask(mask.as_mut_slice(), MATCH_NONE, seqlen, i, KMER);
-                        // ocw.as_mut().and_then(|mut f| {
-                        //     write!(&mut f, "{},{},\"", g,i).unwrap();
-                        //     for m in mask.iter() {
-                        //         write!(&mut f, "{}", m).unwrap();
-                        //     }
-                        //     write!(&mut f, "\"\n", ).unwrap();
-                        //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                        //     Some(())
-                        // });
-                    }
-                }
-            } else {
-                let gplong = gp_to_i64(&shift(gp, i as i32));
-                // ocw.as_mut().and_then(|mut f| {
-                //     write!(&mut f, "{},{},\"", -1,i).unwrap();
-                //     write!(&mut f, "gplong={}\"\n", gplong).unwrap();
-                //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                //     Some(())
-                // });
+            let i = i as i32;
+            for gp in self.kmer_positions(*kmer) {
+                let gplong = gp_to_i64(&shift(gp, i));
+
                 if (gplong - gp1).abs() <= 1 {
                     make_mask(mask.as_mut_slice(), MATCH_TOP, seqlen, i, KMER);
-                    // ocw.as_mut().and_then(|mut f| {
-                    //     write!(&mut f, "-1,{},\"", i).unwrap();
-                    //     for m in mask.iter() {
-                    //         write!(&mut f, "{}", m).unwrap();
-                    //     }
-                    //     write!(&mut f, "\"\n", ).unwrap();
-                    //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                    //     Some(())
-                    // });
                 } else if (gplong - gp2).abs() <= 1 {
                     make_mask(mask.as_mut_slice(), MATCH_SECOND, seqlen, i, KMER);
-                    // ocw.as_mut().and_then(|mut f| {
-                    //     write!(&mut f, "-1,{},\"", i).unwrap();
-                    //     for m in mask.iter() {
-                    //         write!(&mut f, "{}", m).unwrap();
-                    //     }
-                    //     write!(&mut f, "\"\n", ).unwrap();
-                    //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                    //     Some(())
-                    // });
+                } else if gp3.is_some_and(|gp3| (gplong - gp3).abs() <= 1) {
+                    make_mask(mask.as_mut_slice(), MATCH_THIRD, seqlen, i, KMER);
                 } else if gplong == 0 {
                     make_mask(mask.as_mut_slice(), MATCH_NONE, seqlen, i, KMER);
-                    // ocw.as_mut().and_then(|mut f| {
-                    //     write!(&mut f, "-1,{},\"", i).unwrap();
-                    //     for m in mask.iter() {
-                    //         write!(&mut f, "{}", m).unwrap();
-                    //     }
-                    //     write!(&mut f, "\"\n", ).unwrap();
-                    //     // write_tsv_row!(&mut f, i, gp.contig, "2nd-pass");
-                    //     Some(())
-                    // });
                 }
             }
         }
 
         let mut mismatches = 0;
-        mask.iter_mut().take(seqlen as usize).for_each(|m| {
+        let mut third_only = 0;
+        mask.iter().for_each(|m| {
             if *m == MATCH_NONE || *m == MATCH_UNKNOWN {
                 mismatches += 1;
+            } else if *m == MATCH_THIRD {
+                third_only += 1;
             }
         });
 
         if mismatches > global_settings().mismatch_threshold {
             // too many mismatch indicates not a real fusion
+            return Vec::new();
+        }
+
+        let mut result = segment_mask(mask.as_slice(), seqlen, i64_to_gp(gp1), i64_to_gp(gp2));
+
+        if let Some(gp3) = gp3 {
+            if result.len() == 2 {
+                if let Some((start, end)) =
+                    longest_segment(mask.as_slice(), seqlen, MATCH_THIRD as i32)
+                {
+                    result.push(SeqMatch::new(start, end, i64_to_gp(gp3)));
+                    result.sort_by_key(|m| m.seq_start);
+
+                    if result.windows(2).all(|w| w[0].seq_end < w[1].seq_start) {
+                        return result;
+                    }
+
+                    result.retain(|m| m.seq_start != start || m.seq_end != end);
+                }
+            }
+        }
 
-            // log::debug!("map_read, return empty vec. {}:{}", file!(), line!());
+        // without a third segment, its bases are simply mismatches of the two segment match
+        if mismatches + third_only > global_settings().mismatch_threshold {
             return Vec::new();
         }
 
-        segment_mask(mask.as_slice(), seqlen, i64_to_gp(gp1), i64_to_gp(gp2))
+        result
     }
 
     /// this function is to gurantee that all the supporting reads will have same direction
@@ -608,74 +635,102 @@ impl Indexer {
     }
 
     fn print_stat(&self) {
-        println!("m_unique_pos:{}", self.m_unique_pos);
-        println!("m_dupe_pos:{}", self.m_dupe_pos);
+        log::info!("m_unique_pos:{}", self.m_unique_pos);
+        log::info!("m_dupe_pos:{}", self.m_dupe_pos);
     }
 }
 
+/// whether two voted positions are on the same contig within a deletion of each other
+fn near_hit(a: i64, b: i64) -> bool {
+    let (a, b) = (i64_to_gp(a), i64_to_gp(b));
+    a.contig == b.contig
+        && (a.position.abs() - b.position.abs()).abs() <= global_settings().deletion_threshold as i32
+}
+
+/// find the best third voted position which is not just a shifted copy of gp1 or gp2
+fn third_hit(votes: &KmerVoteTable, gp1: i64, gp2: i64, step: i32) -> Option<i64> {
+    let (gp3, count3) =
+        votes.best_where(|k| k != gp1 && k != gp2 && !near_hit(k, gp1) && !near_hit(k, gp2));
+
+    if count3 * step < global_settings().minor_gene_key_requirement {
+        return None;
+    }
+
+    Some(gp3)
+}
+
 fn segment_mask(mask: &[u8], seqlen: i32, gp1: GenePos, gp2: GenePos) -> Vec<SeqMatch> {
     let mut result: Vec<SeqMatch> = Vec::new();
 
-    const ALLOWED_GAP: i32 = 10;
-    const THRESHOLD_LEN: i32 = 20;
-
     let targets = [MATCH_TOP as i32, MATCH_SECOND as i32];
     let gps = [gp1, gp2];
 
     for (target, gp) in targets.into_iter().zip(gps.into_iter()) {
-        let mut max_start = -1_i32;
-        let mut max_end = -1_i32;
+        if let Some((start, end)) = longest_segment(mask, seqlen, target) {
+            let seq_match = SeqMatch::new(start, end, gp);
+            result.push(seq_match);
+        }
+    }
 
-        // get gp1
-        let mut start = 0_i32;
-        let mut end = 0_i32;
+    result
+}
 
-        loop {
-            // get next start
-            while *mask.get(start as usize).unwrap() as i32 != target && start != seqlen - 1 {
-                start += 1;
-            }
-            // reach the tail
-            if start >= seqlen - 1 {
-                break;
-            }
+/// the longest stretch of `target` in the mask, allowing small gaps of lower flags
+fn longest_segment(mask: &[u8], seqlen: i32, target: i32) -> Option<(i32, i32)> {
+    const ALLOWED_GAP: i32 = 10;
+    const THRESHOLD_LEN: i32 = 20;
 
-            if *mask.get(start as usize).unwrap() as i32 == target {
-                end = start + 1;
-                // get the end
-                let mut g = 0_i32;
-                while (g < ALLOWED_GAP) && (end + g) < seqlen {
-                    if *mask.get((end + g) as usize).unwrap() as i32 > target {
-                        break;
-                    }
+    let mut max_start = -1_i32;
+    let mut max_end = -1_i32;
 
-                    if end + g < seqlen && *mask.get((end + g) as usize).unwrap() as i32 == target {
-                        end += g + 1;
-                        g = 0;
-                        continue;
-                    }
+    // get gp1
+    let mut start = 0_i32;
+    let mut end = 0_i32;
 
-                    g += 1;
+    loop {
+        // get next start
+        while *mask.get(start as usize).unwrap() as i32 != target && start != seqlen - 1 {
+            start += 1;
+        }
+        // reach the tail
+        if start >= seqlen - 1 {
+            break;
+        }
+
+        if *mask.get(start as usize).unwrap() as i32 == target {
+            end = start + 1;
+            // get the end
+            let mut g = 0_i32;
+            while (g < ALLOWED_GAP) && (end + g) < seqlen {
+                if *mask.get((end + g) as usize).unwrap() as i32 > target {
+                    break;
                 }
-                // left shift to remove the mismatched end
-                end -= 1;
-                if end - start > (max_end - max_start) {
-                    max_end = end;
-                    max_start = start;
+
+                if end + g < seqlen && *mask.get((end + g) as usize).unwrap() as i32 == target {
+                    end += g + 1;
+                    g = 0;
+                    continue;
                 }
-                start += 1;
-            } else {
-                // not found
-                break;
+
+                g += 1;
             }
-        }
-        if max_end - max_start > THRESHOLD_LEN {
-            let seq_match = SeqMatch::new(max_start, max_end, gp);
-            result.push(seq_match);
+            // left shift to remove the mismatched end
+            end -= 1;
+            if end - start > (max_end - max_start) {
+                max_end = end;
+                max_start = start;
+            }
+            start += 1;
+        } else {
+            // not found
+            break;
         }
     }
+    if max_end - max_start > THRESHOLD_LEN {
+        return Some((max_start, max_end));
+    }
 
-    result
+    None
 }
 
 #[inline]
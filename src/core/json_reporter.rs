@@ -9,7 +9,17 @@ use crate::core::html_reporter::FUSIONSCAN_VER;
 use crate::genefuse::COMMAND;
 
 use super::fusion_scan::Error;
-use super::{fusion_mapper::FusionMapper, fusion_result::FusionResult};
+use super::allele_fraction::AlleleFraction;
+use super::consensus::JunctionConsensus;
+use super::domain::DomainHit;
+use super::circ_rna::CircRna;
+use super::exon_skip::ExonSkip;
+use super::hotspot::Hotspot;
+use super::index_stat::{GeneIndexStat, KmerUniqueness};
+use super::matcher::AlignableRegion;
+use super::transcript::ReadingFrame;
+use super::rejection::{filter_funnel, FunnelRow};
+use super::{fusion_mapper::FusionMapper, fusion_result::FusionResult, multi_fusion_result::MultiFusionResult};
 
 pub(crate) struct JsonReporter<'f, 's> {
     m_filename: String,
@@ -38,13 +48,23 @@ impl<'f, 's> JsonReporter<'f, 's>
         writeln!(f, "\t\"command\":\"{}\",", COMMAND.get().unwrap())?;
         writeln!(f, "\t\"version\":\"{}\",", FUSIONSCAN_VER)?;
         writeln!(f, "\t\"time\":\"{}\",", Local::now())?;
+        writeln!(
+            f,
+            "\t\"thresholds\":{{{}}},",
+            global_settings()
+                .thresholds()
+                .iter()
+                .map(|(k, v)| format!("\"{}\":{}", k, v))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
         write!(f, "\t\"fusions\":{{")?;
 
         let mut is_first_mut = true;
 
         for (i, fusion) in self.m_fusion_mapper.m_fusion_results.iter().enumerate() {
             let matches = fusion.m_matches.as_slice();
-            if !global_settings().output_deletions && fusion.is_deletion() {
+            if !global_settings().output_deletions && fusion.is_intra_gene_indel() {
                 continue;
             }
             if fusion.is_left_protein_forward() != fusion.is_right_protein_forward() {
@@ -61,6 +81,18 @@ impl<'f, 's> JsonReporter<'f, 's>
             }
 
             writeln!(f, "\t\t\"{}\":{{", fusion.m_title)?;
+                writeln!(f, "\t\t\t\"id\":\"{}\",", fusion.id())?;
+                writeln!(f, "\t\t\t\"event\":{},", fusion.m_event_id)?;
+                writeln!(f, "\t\t\t\"event_type\":\"{}\",", fusion.m_event_type.name())?;
+                writeln!(f, "\t\t\t\"event_size\":{},", fusion.m_event_size)?;
+                if let Some(rp) = &fusion.m_reciprocal {
+                    writeln!(f, "\t\t\t\"reciprocal\":{{",)?;
+                        writeln!(f, "\t\t\t\t\"id\":\"{}\",", rp.m_id)?;
+                        writeln!(f, "\t\t\t\t\"unique\":{},", rp.m_unique)?;
+                        writeln!(f, "\t\t\t\t\"total\":{},", rp.m_total)?;
+                        writeln!(f, "\t\t\t\t\"collapsed\":{}", rp.m_collapsed)?;
+                    writeln!(f, "\t\t\t}}, ",)?;
+                }
                 writeln!(f, "\t\t\t\"left\":{{",)?;
                     writeln!(f, "\t\t\t\t\"gene_name\":\"{}\",", fusion.m_left_gene.m_name)?;
                     writeln!(f, "\t\t\t\t\"gene_chr\":\"{}\",", fusion.m_left_gene.m_chr)?;
@@ -85,6 +117,35 @@ impl<'f, 's> JsonReporter<'f, 's>
                 writeln!(f, "\t\t\t}}, ",)?;
 
             writeln!(f, "\t\t\t\"unique\":{},", fusion.m_unique)?;
+            writeln!(f, "\t\t\t\"spanning_pairs\":{},", fusion.spanning_unique())?;
+            writeln!(f, "\t\t\t\"spanning_total\":{},", fusion.m_spanning_pairs.len())?;
+            Self::print_consensus(f, &fusion.m_consensus)?;
+            Self::print_break_context(f, fusion)?;
+            Self::print_reading_frame(f, &fusion.m_reading_frame)?;
+            if !global_settings().domain_file.is_empty() {
+                Self::print_domains(f, &fusion.m_domains)?;
+            }
+            match fusion.m_read_through {
+                Some(gap) => writeln!(f, "\t\t\t\"read_through_gap\":{},", gap)?,
+                None => writeln!(f, "\t\t\t\"read_through_gap\":null,")?,
+            }
+            if !global_settings().known_fusions_file.is_empty() {
+                let sources = fusion.m_known.m_sources.iter().map(|s| format!("\"{}\"", s)).collect::<Vec<_>>();
+                writeln!(f, "\t\t\t\"known\":\"{}\",", fusion.m_known.m_status.name())?;
+                writeln!(f, "\t\t\t\"known_sources\":[{}],", sources.join(", "))?;
+            }
+            Self::print_confidence(f, fusion)?;
+            Self::print_allele_fraction(f, &fusion.m_allele_fraction)?;
+            if global_settings().rna_mode {
+                writeln!(f, "\t\t\t\"junction_type\":\"{}\",", if fusion.is_canonical() { "canonical" } else { "non-canonical" })?;
+                writeln!(f, "\t\t\t\"exon_boundary_sides\":{},", fusion.m_exon_boundary_sides)?;
+                writeln!(f, "\t\t\t\"snap_shift\":{},", fusion.m_snap_shift)?;
+            }
+            if !global_settings().pon_file.is_empty() {
+                let hit = fusion.m_pon.clone().unwrap_or_default();
+                writeln!(f, "\t\t\t\"pon_samples\":{},", hit.m_samples)?;
+                writeln!(f, "\t\t\t\"pon_frequency\":{:.4},", hit.frequency())?;
+            }
             writeln!(f, "\t\t\t\"reads\":[",)?;
 
             for (m, me) in matches.iter().enumerate() {
@@ -106,12 +167,403 @@ impl<'f, 's> JsonReporter<'f, 's>
 
         }
 
-        writeln!(f, "\n\t}}\n}}\n")?;
+        write!(f, "\n\t}}")?;
+
+        Self::print_complex_fusions(f, &self.m_fusion_mapper.m_multi_fusion_results)?;
+        if !self.m_fusion_mapper.m_exon_skips.is_empty() {
+            Self::print_exon_skips(f, &self.m_fusion_mapper.m_exon_skips)?;
+        }
+        if global_settings().circrna_mode {
+            Self::print_circ_rnas(f, &self.m_fusion_mapper.m_circ_rnas)?;
+        }
+        if !self.m_fusion_mapper.m_hotspots.is_empty() {
+            Self::print_hotspots(f, &self.m_fusion_mapper.m_hotspots)?;
+        }
+        Self::print_index_stats(f, &self.m_fusion_mapper.m_indexer.m_index_stats)?;
+        Self::print_alignable_regions(f, &self.m_fusion_mapper.m_alignable_regions)?;
+        Self::print_filter_funnel(
+            f,
+            &filter_funnel(self.m_fusion_mapper.m_candidate_count, &self.m_fusion_mapper.m_rejections),
+        )?;
+
+        writeln!(f, "\n}}\n")?;
+
+        Ok(())
+    }
+
+    fn print_complex_fusions(f: &mut BufWriter<File>, results: &[MultiFusionResult]) -> Result<(), Error> {
+        writeln!(f, ",")?;
+        write!(f, "\t\"complex_fusions\":{{")?;
+
+        for (i, mfr) in results.iter().enumerate() {
+            if i == 0 {
+                writeln!(f, "")?;
+            } else {
+                write!(f, ",\n")?;
+            }
+
+            writeln!(f, "\t\t\"{}\":{{", mfr.m_title)?;
+            writeln!(f, "\t\t\t\"junctions\":[",)?;
+            for (j, junction) in mfr.m_junctions.iter().enumerate() {
+                let left_gene = &mfr.m_genes[j];
+                let right_gene = &mfr.m_genes[j + 1];
+                writeln!(f, "\t\t\t\t{{",)?;
+                    writeln!(f, "\t\t\t\t\t\"left_gene_name\":\"{}\",", left_gene.m_name)?;
+                    writeln!(f, "\t\t\t\t\t\"left_gene_chr\":\"{}\",", left_gene.m_chr)?;
+                    writeln!(f, "\t\t\t\t\t\"left_position\":{},", left_gene.gene_pos_2_chr_pos(junction.m_left_gp.position))?;
+                    writeln!(f, "\t\t\t\t\t\"left_pos_str\":\"{}\",", mfr.m_left_pos[j])?;
+                    writeln!(f, "\t\t\t\t\t\"right_gene_name\":\"{}\",", right_gene.m_name)?;
+                    writeln!(f, "\t\t\t\t\t\"right_gene_chr\":\"{}\",", right_gene.m_chr)?;
+                    writeln!(f, "\t\t\t\t\t\"right_position\":{},", right_gene.gene_pos_2_chr_pos(junction.m_right_gp.position))?;
+                    writeln!(f, "\t\t\t\t\t\"right_pos_str\":\"{}\"", mfr.m_right_pos[j])?;
+                write!(f, "\t\t\t\t}}")?;
+
+                if j != mfr.m_junctions.len() - 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, "\n")?;
+            }
+            writeln!(f, "\t\t\t],",)?;
+
+            writeln!(f, "\t\t\t\"unique\":{},", mfr.m_unique)?;
+            writeln!(f, "\t\t\t\"reads\":[",)?;
+
+            for (m, me) in mfr.m_matches.iter().enumerate() {
+                writeln!(f, "\t\t\t\t{{",)?;
+                writeln!(
+                    f,
+                    "\t\t\t\t\t\"breaks\":[{}],",
+                    me.m_junctions.iter().map(|j| j.m_read_break.to_string()).collect::<Vec<_>>().join(",")
+                )?;
+                writeln!(f, "\t\t\t\t\t\"strand\":\"{}\",", {if me.m_reversed {"reversed"} else {"forward"}})?;
+                me.print_read_to_json(f, "\t\t\t\t\t")?;
+                write!(f, "\t\t\t\t}}")?;
+
+                if m != mfr.m_matches.len()-1 {
+                    write!(f, ",")?;
+                }
+
+                write!(f, "\n")?;
+            }
+
+            writeln!(f, "\t\t\t]",)?;
+            write!(f, "\t\t}}")?;
+        }
+
+        write!(f, "\n\t}}")?;
+
+        Ok(())
+    }
+
+    fn print_exon_skips(f: &mut BufWriter<File>, skips: &[ExonSkip]) -> Result<(), Error> {
+        writeln!(f, ",")?;
+        write!(f, "\t\"exon_skipping\":[")?;
+
+        for (i, es) in skips.iter().enumerate() {
+            if i == 0 {
+                writeln!(f, "")?;
+            } else {
+                write!(f, ",\n")?;
+            }
+
+            writeln!(f, "\t\t{{",)?;
+                writeln!(f, "\t\t\t\"id\":\"{}\",", es.id())?;
+                writeln!(f, "\t\t\t\"title\":\"{}\",", es.title())?;
+                writeln!(f, "\t\t\t\"gene\":\"{}\",", es.m_gene)?;
+                writeln!(f, "\t\t\t\"chr\":\"{}\",", es.m_chr)?;
+                writeln!(f, "\t\t\t\"left_exon\":{},", es.m_left_exon)?;
+                writeln!(f, "\t\t\t\"right_exon\":{},", es.m_right_exon)?;
+                writeln!(f, "\t\t\t\"skipped_exons\":\"{}\",", es.skipped())?;
+                writeln!(f, "\t\t\t\"left_position\":{},", es.m_left_pos)?;
+                writeln!(f, "\t\t\t\"right_position\":{},", es.m_right_pos)?;
+                writeln!(f, "\t\t\t\"unique\":{},", es.m_unique)?;
+                writeln!(f, "\t\t\t\"reads\":{},", es.m_reads)?;
+                writeln!(f, "\t\t\t\"left_normal_reads\":{},", es.m_left_normal_reads)?;
+                writeln!(f, "\t\t\t\"right_normal_reads\":{}", es.m_right_normal_reads)?;
+            write!(f, "\t\t}}")?;
+        }
+
+        write!(f, "\n\t]")?;
 
         Ok(())
     }
 
+    fn print_circ_rnas(f: &mut BufWriter<File>, circs: &[CircRna]) -> Result<(), Error> {
+        writeln!(f, ",")?;
+        write!(f, "\t\"circ_rnas\":[")?;
 
+        for (i, c) in circs.iter().enumerate() {
+            if i == 0 {
+                writeln!(f, "")?;
+            } else {
+                write!(f, ",\n")?;
+            }
 
+            writeln!(f, "\t\t{{",)?;
+                writeln!(f, "\t\t\t\"id\":\"{}\",", c.id())?;
+                writeln!(f, "\t\t\t\"gene\":\"{}\",", c.m_gene)?;
+                writeln!(f, "\t\t\t\"chr\":\"{}\",", c.m_chr)?;
+                writeln!(f, "\t\t\t\"first_exon\":{},", c.m_first_exon)?;
+                writeln!(f, "\t\t\t\"last_exon\":{},", c.m_last_exon)?;
+                writeln!(f, "\t\t\t\"donor_position\":{},", c.m_donor_pos)?;
+                writeln!(f, "\t\t\t\"acceptor_position\":{},", c.m_acceptor_pos)?;
+                writeln!(f, "\t\t\t\"unique\":{},", c.m_unique)?;
+                writeln!(f, "\t\t\t\"back_splice_reads\":{},", c.m_reads)?;
+                writeln!(f, "\t\t\t\"donor_linear_reads\":{},", c.m_donor_linear_reads)?;
+                writeln!(f, "\t\t\t\"acceptor_linear_reads\":{},", c.m_acceptor_linear_reads)?;
+                match c.circular_linear_ratio() {
+                    Some(r) => writeln!(f, "\t\t\t\"circular_linear_ratio\":{:.4}", r)?,
+                    None => writeln!(f, "\t\t\t\"circular_linear_ratio\":null")?,
+                }
+            write!(f, "\t\t}}")?;
+        }
+
+        write!(f, "\n\t]")?;
+
+        Ok(())
+    }
+
+    fn print_hotspots(f: &mut BufWriter<File>, hotspots: &[Hotspot]) -> Result<(), Error> {
+        writeln!(f, ",")?;
+        write!(f, "\t\"hotspots\":[")?;
+
+        for (i, hotspot) in hotspots.iter().enumerate() {
+            if i == 0 {
+                writeln!(f, "")?;
+            } else {
+                write!(f, ",\n")?;
+            }
 
-}
\ No newline at end of file
+            writeln!(f, "\t\t{{",)?;
+                writeln!(f, "\t\t\t\"id\":\"{}\",", hotspot.id())?;
+                writeln!(f, "\t\t\t\"left_gene_name\":\"{}\",", hotspot.m_left_gene.m_name)?;
+                writeln!(f, "\t\t\t\"left_gene_chr\":\"{}\",", hotspot.m_left_gene.m_chr)?;
+                writeln!(f, "\t\t\t\"left_position\":{},", hotspot.m_left_gene.gene_pos_2_chr_pos(hotspot.m_left_gp.position))?;
+                writeln!(f, "\t\t\t\"right_gene_name\":\"{}\",", hotspot.m_right_gene.m_name)?;
+                writeln!(f, "\t\t\t\"right_gene_chr\":\"{}\",", hotspot.m_right_gene.m_chr)?;
+                writeln!(f, "\t\t\t\"right_position\":{},", hotspot.m_right_gene.gene_pos_2_chr_pos(hotspot.m_right_gp.position))?;
+                writeln!(f, "\t\t\t\"junction\":\"{}\",", hotspot.junction_seq(20))?;
+                writeln!(f, "\t\t\t\"genotype\":\"{}\",", hotspot.genotype())?;
+                let af = hotspot.allele_fraction();
+                writeln!(f, "\t\t\t\"fusion_reads\":{},", af.m_alt_reads)?;
+                writeln!(f, "\t\t\t\"left_ref_reads\":{},", af.m_left_ref_reads)?;
+                writeln!(f, "\t\t\t\"right_ref_reads\":{},", af.m_right_ref_reads)?;
+                writeln!(f, "\t\t\t\"vaf\":{:.4},", af.m_vaf)?;
+                writeln!(f, "\t\t\t\"vaf_ci\":[{:.4}, {:.4}]", af.m_ci_low, af.m_ci_high)?;
+            write!(f, "\t\t}}")?;
+        }
+
+        write!(f, "\n\t]")?;
+
+        Ok(())
+    }
+
+    fn print_index_stats(f: &mut BufWriter<File>, stats: &[GeneIndexStat]) -> Result<(), Error> {
+        writeln!(f, ",")?;
+        write!(f, "\t\"index_stats\":{{")?;
+
+        for (i, gs) in stats.iter().enumerate() {
+            if i == 0 {
+                writeln!(f, "")?;
+            } else {
+                write!(f, ",\n")?;
+            }
+
+            writeln!(f, "\t\t\"{}\":{{", gs.name)?;
+            writeln!(f, "\t\t\t\"chr\":\"{}\",", gs.chr)?;
+            writeln!(f, "\t\t\t\"start\":{},", gs.start)?;
+            writeln!(f, "\t\t\t\"end\":{},", gs.end)?;
+            Self::print_kmer_uniqueness(f, &gs.kmers, "\t\t\t")?;
+            writeln!(f, ",")?;
+            writeln!(f, "\t\t\t\"exons\":[",)?;
+
+            for (e, es) in gs.exons.iter().enumerate() {
+                writeln!(f, "\t\t\t\t{{",)?;
+                    writeln!(f, "\t\t\t\t\t\"id\":{},", es.id)?;
+                    writeln!(f, "\t\t\t\t\t\"start\":{},", es.start)?;
+                    writeln!(f, "\t\t\t\t\t\"end\":{},", es.end)?;
+                    Self::print_kmer_uniqueness(f, &es.kmers, "\t\t\t\t\t")?;
+                write!(f, "\n\t\t\t\t}}")?;
+
+                if e != gs.exons.len() - 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, "\n")?;
+            }
+
+            writeln!(f, "\t\t\t]",)?;
+            write!(f, "\t\t}}")?;
+        }
+
+        write!(f, "\n\t}}")?;
+
+        Ok(())
+    }
+
+    fn print_alignable_regions(f: &mut BufWriter<File>, regions: &[AlignableRegion]) -> Result<(), Error> {
+        writeln!(f, ",")?;
+        write!(f, "\t\"alignable_removed\":[")?;
+
+        for (i, ar) in regions.iter().enumerate() {
+            if i == 0 {
+                writeln!(f, "")?;
+            } else {
+                write!(f, ",\n")?;
+            }
+
+            write!(
+                f,
+                "\t\t{{\"contig\":\"{}\", \"start\":{}, \"end\":{}, \"reads\":{}}}",
+                ar.contig, ar.start, ar.end, ar.reads
+            )?;
+        }
+
+        write!(f, "\n\t]")?;
+
+        Ok(())
+    }
+
+    fn print_filter_funnel(f: &mut BufWriter<File>, funnel: &[FunnelRow]) -> Result<(), Error> {
+        writeln!(f, ",")?;
+        write!(f, "\t\"filter_funnel\":[")?;
+
+        for (i, row) in funnel.iter().enumerate() {
+            if i == 0 {
+                writeln!(f, "")?;
+            } else {
+                write!(f, ",\n")?;
+            }
+
+            write!(
+                f,
+                "\t\t{{\"stage\":\"{}\", \"removed\":{}, \"remaining\":{}}}",
+                row.stage, row.removed, row.remaining
+            )?;
+        }
+
+        write!(f, "\n\t]")?;
+
+        Ok(())
+    }
+
+    fn print_confidence(f: &mut BufWriter<File>, fusion: &FusionResult) -> Result<(), Error> {
+        let c = &fusion.m_confidence;
+
+        writeln!(f, "\t\t\t\"confidence\":{{")?;
+        writeln!(f, "\t\t\t\t\"score\":{:.4},", fusion.m_score)?;
+        write!(f, "\t\t\t\t\"components\":{{")?;
+        for (i, (name, value)) in c.components(global_settings().diff_threshold).iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "\"{}\":{:.4}", name, value)?;
+        }
+        writeln!(f, "}},")?;
+        writeln!(
+            f,
+            "\t\t\t\t\"raw\":{{\"unique\":{}, \"mean_edit_distance\":{:.4}, \"mean_short_segment\":{:.4}, \"kmer_unique_ratio\":{:.4}, \"forward_reads\":{}, \"reversed_reads\":{}, \"exon_boundaries\":{}}}",
+            c.unique,
+            c.mean_edit_distance,
+            c.mean_short_segment,
+            c.kmer_unique_ratio,
+            c.forward_reads,
+            c.reversed_reads,
+            c.exon_boundaries
+        )?;
+        writeln!(f, "\t\t\t}},")?;
+
+        Ok(())
+    }
+
+    fn print_allele_fraction(f: &mut BufWriter<File>, af: &AlleleFraction) -> Result<(), Error> {
+        writeln!(f, "\t\t\t\"left_ref_reads\":{},", af.m_left_ref_reads)?;
+        writeln!(f, "\t\t\t\"right_ref_reads\":{},", af.m_right_ref_reads)?;
+        writeln!(f, "\t\t\t\"vaf\":{:.4},", af.m_vaf)?;
+        writeln!(f, "\t\t\t\"vaf_ci\":[{:.4}, {:.4}],", af.m_ci_low, af.m_ci_high)?;
+
+        Ok(())
+    }
+
+    fn print_break_context(f: &mut BufWriter<File>, fusion: &FusionResult) -> Result<(), Error> {
+        let bc = &fusion.m_break_context;
+        let ((left_first, left_last), (right_first, right_last)) = fusion.break_ranges();
+
+        writeln!(f, "\t\t\t\"break_context\":{{")?;
+        writeln!(f, "\t\t\t\t\"donor_motif\":\"{}\",", bc.m_donor)?;
+        writeln!(f, "\t\t\t\t\"acceptor_motif\":\"{}\",", bc.m_acceptor)?;
+        writeln!(f, "\t\t\t\t\"splice_motif\":\"{}\",", bc.splice_class())?;
+        writeln!(f, "\t\t\t\t\"microhomology\":\"{}\",", bc.m_microhomology)?;
+        writeln!(f, "\t\t\t\t\"microhomology_length\":{},", bc.microhomology_len())?;
+        writeln!(f, "\t\t\t\t\"left_break_range\":[{}, {}],", left_first, left_last)?;
+        writeln!(f, "\t\t\t\t\"right_break_range\":[{}, {}]", right_first, right_last)?;
+        writeln!(f, "\t\t\t}},")?;
+
+        Ok(())
+    }
+
+    fn print_reading_frame(f: &mut BufWriter<File>, frame: &ReadingFrame) -> Result<(), Error> {
+        writeln!(f, "\t\t\t\"reading_frame\":\"{}\",", frame.m_type.name())?;
+        writeln!(f, "\t\t\t\"protein_length\":{},", frame.protein_length())?;
+        match frame.m_premature_stop {
+            Some(stop) => writeln!(f, "\t\t\t\"premature_stop\":{},", stop)?,
+            None => writeln!(f, "\t\t\t\"premature_stop\":null,")?,
+        }
+
+        Ok(())
+    }
+
+    fn print_domains(f: &mut BufWriter<File>, domains: &[DomainHit]) -> Result<(), Error> {
+        writeln!(f, "\t\t\t\"domains\":[")?;
+        for (i, hit) in domains.iter().enumerate() {
+            let d = &hit.m_domain;
+            write!(
+                f,
+                "\t\t\t\t{{\"gene\":\"{}\", \"domain\":\"{}\", \"start\":{}, \"end\":{}, \"partner\":\"{}\", \"status\":\"{}\"}}",
+                d.m_gene,
+                d.m_name,
+                d.m_start,
+                d.m_end,
+                hit.partner(),
+                hit.m_retention.name()
+            )?;
+            writeln!(f, "{}", if i + 1 < domains.len() { "," } else { "" })?;
+        }
+        writeln!(f, "\t\t\t],")?;
+
+        Ok(())
+    }
+
+    fn print_consensus(f: &mut BufWriter<File>, cons: &JunctionConsensus) -> Result<(), Error> {
+        let join = |v: &[u32]| v.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(",");
+
+        writeln!(f, "\t\t\t\"consensus\":{{")?;
+        writeln!(f, "\t\t\t\t\"seq\":\"{}\",", cons.m_seq)?;
+        writeln!(f, "\t\t\t\t\"qual\":\"{}\",", json_escape(&cons.m_qual))?;
+        writeln!(f, "\t\t\t\t\"break\":{},", cons.m_break)?;
+        writeln!(f, "\t\t\t\t\"depth\":[{}],", join(&cons.m_depth))?;
+        writeln!(f, "\t\t\t\t\"support\":[{}]", join(&cons.m_support))?;
+        writeln!(f, "\t\t\t}},")?;
+
+        Ok(())
+    }
+
+    fn print_kmer_uniqueness(f: &mut BufWriter<File>, kmers: &KmerUniqueness, pad: &str) -> Result<(), Error> {
+        writeln!(f, "{}\"kmers\":{},", pad, kmers.total())?;
+        writeln!(f, "{}\"unique\":{},", pad, kmers.unique)?;
+        writeln!(f, "{}\"normal_dupe\":{},", pad, kmers.normal_dupe)?;
+        writeln!(f, "{}\"high_dupe\":{},", pad, kmers.high_dupe)?;
+        writeln!(f, "{}\"unique_ratio\":{:.4},", pad, kmers.unique_ratio())?;
+        write!(f, "{}\"flag\":\"{}\"", pad, kmers.flag())?;
+
+        Ok(())
+    }
+
+
+
+
+
+}
+
+fn json_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
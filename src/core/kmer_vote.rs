@@ -0,0 +1,260 @@
+use std::cell::RefCell;
+
+const KMER: usize = 16;
+const KMER_BITS: i64 = 0xFFFFFFFF;
+const INITIAL_CAPACITY: usize = 1024;
+
+/// Encode every 16-mer of `seq` with 2 bits per base (A=0, T=1, C=2, G=3), the same encoding
+/// as the index. `kmers[i]` is the k-mer starting at `i`, or -1 if it contains other bases.
+pub fn encode_kmers(seq: &[u8], kmers: &mut Vec<i64>) {
+    kmers.clear();
+
+    let mut kmer = 0_i64;
+    let mut valid = 0_usize;
+    for (i, base) in seq.iter().enumerate() {
+        let code = match base {
+            b'A' => 0,
+            b'T' => 1,
+            b'C' => 2,
+            b'G' => 3,
+            _ => -1,
+        };
+
+        if code < 0 {
+            kmer = 0;
+            valid = 0;
+        } else {
+            kmer = ((kmer << 2) | code) & KMER_BITS;
+            valid += 1;
+        }
+
+        if i + 1 >= KMER {
+            kmers.push(if valid >= KMER { kmer } else { -1 });
+        }
+    }
+}
+
+/// Vote counter for gene positions, replacing a `BTreeMap<i64, i32>` per read.
+///
+/// It's an open addressing table stamped with a generation number, so `clear()` is O(1)
+/// and the memory is reused by the next read.
+pub struct KmerVoteTable {
+    keys: Vec<i64>,
+    counts: Vec<i32>,
+    stamps: Vec<u32>,
+    generation: u32,
+    // slots used in current generation, in insertion order
+    used: Vec<usize>,
+    bits: u32,
+}
+
+impl Default for KmerVoteTable {
+    fn default() -> Self {
+        Self::with_capacity(INITIAL_CAPACITY)
+    }
+}
+
+impl KmerVoteTable {
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.next_power_of_two().max(16);
+
+        Self {
+            keys: vec![0; capacity],
+            counts: vec![0; capacity],
+            stamps: vec![0; capacity],
+            generation: 1,
+            used: Vec::with_capacity(capacity / 2),
+            bits: capacity.trailing_zeros(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.used.clear();
+        self.generation = self.generation.wrapping_add(1);
+
+        if self.generation == 0 {
+            self.stamps.iter_mut().for_each(|s| *s = 0);
+            self.generation = 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.used.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.used.is_empty()
+    }
+
+    #[inline]
+    fn slot_of(&self, key: i64) -> usize {
+        // fibonacci hashing, gene positions of a read are clustered in a small range
+        ((key as u64).wrapping_mul(0x9E3779B97F4A7C15) >> (64 - self.bits)) as usize
+    }
+
+    #[inline]
+    pub fn vote(&mut self, key: i64) {
+        if (self.used.len() + 1) * 2 > self.keys.len() {
+            self.grow();
+        }
+
+        let mask = self.keys.len() - 1;
+        let mut slot = self.slot_of(key);
+        loop {
+            if self.stamps[slot] != self.generation {
+                self.stamps[slot] = self.generation;
+                self.keys[slot] = key;
+                self.counts[slot] = 1;
+                self.used.push(slot);
+                return;
+            }
+
+            if self.keys[slot] == key {
+                self.counts[slot] += 1;
+                return;
+            }
+
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    pub fn count(&self, key: i64) -> i32 {
+        let mask = self.keys.len() - 1;
+        let mut slot = self.slot_of(key);
+        while self.stamps[slot] == self.generation {
+            if self.keys[slot] == key {
+                return self.counts[slot];
+            }
+            slot = (slot + 1) & mask;
+        }
+
+        0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i64, i32)> + '_ {
+        self.used
+            .iter()
+            .map(|slot| (self.keys[*slot], self.counts[*slot]))
+    }
+
+    /// The two best voted keys, ordered by count then by the smaller key.
+    /// It gives the same result as scanning a `BTreeMap` in key order and keeping strictly
+    /// greater counts. `(0, 0)` is returned for a missing one.
+    pub fn top2(&self) -> ((i64, i32), (i64, i32)) {
+        let mut first = (0_i64, 0_i32);
+        let mut second = (0_i64, 0_i32);
+
+        for (k, v) in self.iter() {
+            if better(k, v, first) {
+                second = first;
+                first = (k, v);
+            } else if better(k, v, second) {
+                second = (k, v);
+            }
+        }
+
+        (first, second)
+    }
+
+    /// The best voted key accepted by `filter`, with the same ordering as `top2()`.
+    pub fn best_where(&self, mut filter: impl FnMut(i64) -> bool) -> (i64, i32) {
+        let mut best = (0_i64, 0_i32);
+
+        for (k, v) in self.iter() {
+            if better(k, v, best) && filter(k) {
+                best = (k, v);
+            }
+        }
+
+        best
+    }
+
+    fn grow(&mut self) {
+        let entries = self.iter().collect::<Vec<_>>();
+        let mut grown = Self::with_capacity(self.keys.len() * 2);
+        for (k, v) in entries {
+            grown.vote(k);
+            let slot = *grown.used.last().unwrap();
+            grown.counts[slot] = v;
+        }
+
+        *self = grown;
+    }
+}
+
+#[inline]
+fn better(key: i64, count: i32, than: (i64, i32)) -> bool {
+    count > than.1 || (count == than.1 && count > 0 && key < than.0)
+}
+
+/// Buffers reused by every `Indexer::map_read()` call on the same thread
+pub(crate) struct MapReadBuffers {
+    pub(crate) kmers: Vec<i64>,
+    pub(crate) votes: KmerVoteTable,
+    pub(crate) mask: Vec<u8>,
+}
+
+thread_local! {
+    pub(crate) static MAP_READ_BUFFERS: RefCell<MapReadBuffers> = RefCell::new(MapReadBuffers {
+        kmers: Vec::new(),
+        votes: KmerVoteTable::default(),
+        mask: Vec::new(),
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn encode_like_index() {
+        let mut kmers = Vec::new();
+        encode_kmers(b"ATCGATCGATCGATCGANAAAAAAAAAAAAAAAAA", &mut kmers);
+
+        assert_eq!(kmers.len(), 35 - KMER + 1);
+        // A=0, T=1, C=2, G=3
+        assert_eq!(kmers[0], 0b00011011_00011011_00011011_00011011);
+        assert_eq!(kmers[1], (kmers[0] << 2 | 0) & KMER_BITS);
+        assert!(kmers[2..18].iter().all(|k| *k == -1));
+        assert_eq!(kmers[18], 0);
+    }
+
+    #[test]
+    fn top2_same_as_btreemap() {
+        let keys = [7_i64, -3, 12, 7, 40, -3, 12, 12, 5, 5, 5, 40, 1 << 33, 1 << 33, 1 << 33];
+
+        // a small table to go through grow()
+        let mut table = KmerVoteTable::with_capacity(4);
+        let mut map: BTreeMap<i64, i32> = BTreeMap::new();
+
+        for round in 0..3 {
+            table.clear();
+            map.clear();
+            for k in keys.iter().skip(round) {
+                table.vote(*k);
+                *map.entry(*k).or_insert(0) += 1;
+            }
+
+            let (mut gp1, mut count1, mut gp2, mut count2) = (0, 0, 0, 0);
+            for (k, v) in map.iter() {
+                if *v > count1 {
+                    gp2 = gp1;
+                    count2 = count1;
+                    gp1 = *k;
+                    count1 = *v;
+                } else if *v > count2 {
+                    gp2 = *k;
+                    count2 = *v;
+                }
+            }
+
+            assert_eq!(table.top2(), ((gp1, count1), (gp2, count2)));
+            assert_eq!(table.len(), map.len());
+            for (k, v) in map.iter() {
+                assert_eq!(table.count(*k), *v);
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use super::{fusion_scan::Error, gene::Gene};
+
+/// How a fusion compares with the database of known fusions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) enum KnownStatus {
+    #[default]
+    Novel,
+    // the 5' and the 3' gene are fused in the database
+    KnownPair,
+    // with the same exons joined
+    KnownJunction,
+}
+
+impl KnownStatus {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            KnownStatus::Novel => "novel",
+            KnownStatus::KnownPair => "known_pair",
+            KnownStatus::KnownJunction => "known_junction",
+        }
+    }
+
+    pub(crate) fn title(&self) -> &'static str {
+        match self {
+            KnownStatus::Novel => "novel",
+            KnownStatus::KnownPair => "known gene pair",
+            KnownStatus::KnownJunction => "known exon junction",
+        }
+    }
+}
+
+/// The best match of a fusion in the database and the sources reporting it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct KnownHit {
+    pub(crate) m_status: KnownStatus,
+    pub(crate) m_sources: Vec<String>,
+}
+
+/// A fusion of the database, the 5' gene first, with the last exon of the 5' gene and the first
+/// exon of the 3' gene when the junction is known.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KnownFusion {
+    pub(crate) m_five_gene: String,
+    pub(crate) m_three_gene: String,
+    pub(crate) m_exons: Option<(i32, i32)>,
+    pub(crate) m_source: String,
+}
+
+// `13`, `exon13`, or nothing for a gene pair
+fn parse_exon(s: &str) -> Result<Option<i32>, Error> {
+    let s = s.trim();
+    if s.is_empty() || s == "." {
+        return Ok(None);
+    }
+    let s = s.strip_prefix("exon").unwrap_or(s);
+
+    Ok(Some(s.parse()?))
+}
+
+fn is_gene(name: &str, gene: &Gene) -> bool {
+    name.eq_ignore_ascii_case(&gene.m_name) || name.eq_ignore_ascii_case(gene.symbol())
+}
+
+/// Known fusions, such as exports of COSMIC or Mitelman.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct KnownFusions {
+    pub(crate) m_fusions: Vec<KnownFusion>,
+}
+
+impl KnownFusions {
+    /// TSV of the 5' gene, the 3' gene and optionally the 5' exon, the 3' exon and a source
+    pub(crate) fn read(file: &str) -> Result<Self, Error> {
+        let reader = BufReader::new(File::open(file)?);
+        let mut known = Self::default();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let cols = line.split('\t').map(|c| c.trim()).collect::<Vec<_>>();
+            if cols.len() < 2 {
+                return Err(format!("{}:{}: a known fusion line needs the 5' and the 3' gene", file, i + 1).into());
+            }
+            let col = |c: usize| cols.get(c).copied().unwrap_or_default();
+            // a header without '#'
+            if i == 0 && parse_exon(col(2)).is_err() {
+                continue;
+            }
+
+            let exons = match (parse_exon(col(2))?, parse_exon(col(3))?) {
+                (Some(five), Some(three)) => Some((five, three)),
+                (None, None) => None,
+                _ => return Err(format!("{}:{}: a known junction needs both exons", file, i + 1).into()),
+            };
+            known.m_fusions.push(KnownFusion {
+                m_five_gene: cols[0].to_owned(),
+                m_three_gene: cols[1].to_owned(),
+                m_exons: exons,
+                m_source: col(4).to_owned(),
+            });
+        }
+
+        Ok(known)
+    }
+
+    /// `five` fused to `three` joining `exons`, as given by `FusionResult::junction_exons`
+    pub(crate) fn find(&self, five: &Gene, three: &Gene, exons: Option<(i32, i32)>) -> KnownHit {
+        let mut hit = KnownHit::default();
+
+        for kf in self.m_fusions.iter().filter(|kf| is_gene(&kf.m_five_gene, five) && is_gene(&kf.m_three_gene, three)) {
+            let status = if kf.m_exons.is_some() && kf.m_exons == exons {
+                KnownStatus::KnownJunction
+            } else {
+                KnownStatus::KnownPair
+            };
+            if status > hit.m_status {
+                hit.m_status = status;
+                hit.m_sources.clear();
+            }
+            if status == hit.m_status && !kf.m_source.is_empty() && !hit.m_sources.contains(&kf.m_source) {
+                hit.m_sources.push(kf.m_source.clone());
+            }
+        }
+
+        hit
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn known(five: &str, three: &str, exons: Option<(i32, i32)>, source: &str) -> KnownFusion {
+        KnownFusion {
+            m_five_gene: five.to_string(),
+            m_three_gene: three.to_string(),
+            m_exons: exons,
+            m_source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn known_pairs_and_junctions() {
+        assert_eq!(parse_exon("exon13").unwrap(), Some(13));
+        assert_eq!(parse_exon(".").unwrap(), None);
+        assert!(parse_exon("five_exon").is_err());
+
+        let known = KnownFusions {
+            m_fusions: vec![
+                known("EML4", "ALK", Some((13, 20)), "COSMIC"),
+                known("EML4", "ALK", Some((6, 20)), "COSMIC"),
+                known("EML4", "ALK", None, "Mitelman"),
+                known("BCR", "ABL1", None, ""),
+            ],
+        };
+        let eml4 = Gene::new("EML4_NM_019063".to_string(), "chr2".to_string(), 0, 1000);
+        let alk = Gene::new("ALK".to_string(), "chr2".to_string(), 0, 1000);
+
+        let hit = known.find(&eml4, &alk, Some((13, 20)));
+        assert_eq!(hit.m_status, KnownStatus::KnownJunction);
+        assert_eq!(hit.m_sources, vec!["COSMIC"]);
+
+        let hit = known.find(&eml4, &alk, Some((14, 20)));
+        assert_eq!(hit.m_status, KnownStatus::KnownPair);
+        assert_eq!(hit.m_sources, vec!["COSMIC", "Mitelman"]);
+
+        // the genes are ordered 5' to 3'
+        assert_eq!(known.find(&alk, &eml4, Some((13, 20))), KnownHit::default());
+    }
+}
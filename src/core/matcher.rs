@@ -8,7 +8,7 @@ use std::{
 use crate::aux::int_hasher::{CPPTrivialHasherBuilder, FxHasherBuilder};
 use rayon::{iter::{IntoParallelRefIterator, ParallelBridge, ParallelIterator}, ThreadPool};
 
-use super::{common::GenePos, fasta_reader::FastaReader, sequence::Sequence};
+use super::{common::GenePos, exclude_region::ExcludeRegions, fasta_reader::FastaReader, sequence::Sequence};
 
 pub(crate) type GFHasherBuilder = FxHasherBuilder;
 
@@ -25,6 +25,18 @@ impl MatchResult {
     }
 }
 
+// removed reads are counted per reference window of this size
+pub(crate) const ALIGNABLE_REGION_BIN: i32 = 10_000;
+
+/// A reference window, 0-based half-open, that candidate reads aligned to contiguously.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AlignableRegion {
+    pub(crate) contig: String,
+    pub(crate) start: i32,
+    pub(crate) end: i32,
+    pub(crate) reads: usize,
+}
+
 // we use 512M memory
 const BLOOM_FILTER_LENGTH: usize = 1 << 29;
 const KMER: i32 = 16;
@@ -44,6 +56,7 @@ impl Matcher {
     pub(crate) fn from_ref_and_seqs(
         fasta_ref: Arc<FastaReader>,
         seqs: &[Sequence],
+        exclude: &ExcludeRegions,
         inner_thread_pool:Option<&ThreadPool>,
     ) -> Self {
         let mut matcher = Self {
@@ -56,14 +69,12 @@ impl Matcher {
         };
 
         matcher.init_bloom_filter(seqs);
-        matcher.make_index(inner_thread_pool);
+        matcher.make_index(exclude, inner_thread_pool);
 
         matcher
     }
 
     fn init_bloom_filter(&mut self, seqs: &[Sequence]) -> () {
-        let m_bloom_filter_array: [u8; BLOOM_FILTER_LENGTH] = [0; BLOOM_FILTER_LENGTH];
-
         for seq in seqs.iter() {
             self.init_bloom_filter_with_seq(seq);
             self.init_bloom_filter_with_seq(&seq.reverse_complement());
@@ -117,14 +128,15 @@ impl Matcher {
 
     // }
 
-    fn make_index(&mut self, itp:Option<&ThreadPool>) {
+    fn make_index(&mut self, exclude: &ExcludeRegions, itp:Option<&ThreadPool>) {
         if self.m_reference.is_none() {
             return;
         }
 
         let contig_ref = &Arc::clone(self.m_reference.as_ref().unwrap()).m_all_contigs;
         // let contig_ref = mem::take(&mut self.m_reference.as_mut().unwrap().m_all_contigs);
-        let m_contig_names = Mutex::new(mem::take(&mut self.m_contig_names));
+        // names are kept in contig order, GenePos.contig indexes into them.
+        self.m_contig_names = contig_ref.keys().cloned().collect();
         let m_kmer_positions = Mutex::new(mem::take(&mut self.m_kmer_positions));
 
         // let ctg = AtomicI32::new(0);
@@ -134,14 +146,19 @@ impl Matcher {
 
         let do_index_contig = |(ctg, e):(usize, (&String, &String))| {
             let (ctg_name, s) = (e.0.as_str(), e.1.as_str());
-            let seq_cv = s
+            if exclude.is_contig_excluded(ctg_name) {
+                log::debug!("contig {} is excluded from matcher index.", ctg_name);
+                return;
+            }
+
+            let mut seq_cv = s
                 .as_bytes()
                 .iter()
                 .copied()
                 .map(|b| b.to_ascii_uppercase())
                 .collect::<Vec<u8>>();
             // let s = s.to_uppercase();
-            m_contig_names.lock().unwrap().push(ctg_name.to_owned());
+            exclude.mask(ctg_name, &mut seq_cv);
 
             //index forward
             self.index_contig_bytes(ctg as i32, &seq_cv, 0, &m_kmer_positions);
@@ -165,21 +182,15 @@ impl Matcher {
 
         // self.m_reference.as_mut().unwrap().m_all_contigs = contig_ref;
         self.m_kmer_positions = m_kmer_positions.into_inner().unwrap();
-        self.m_contig_names = m_contig_names.into_inner().unwrap();
     }
 
     fn index_contig_cv(&mut self, ctg: i32, seq: &[char], start: i32) {
         let mut kmer = 0_u32;
         let mut valid = false;
 
-        for (base, i) in seq
-            .get(..((seq.len() as i32 - KMER) as usize))
-            .unwrap()
-            .iter()
-            .zip(0..)
-        {
+        for i in 0..(seq.len() as i32 - KMER) {
             if valid {
-                // let base = seq.get((i + KMER - 1) as usize).unwrap();
+                let base = seq.get((i + KMER - 1) as usize).unwrap();
                 let num = base2num(*base);
 
                 if num < 0 {
@@ -234,14 +245,9 @@ impl Matcher {
         let mut kmer = 0_u32;
         let mut valid = false;
 
-        for (base, i) in seq
-            .get(..((seq.len() as i32 - KMER) as usize))
-            .unwrap()
-            .iter()
-            .zip(0..)
-        {
+        for i in 0..(seq.len() as i32 - KMER) {
             if valid {
-                // let base = seq.get((i + KMER - 1) as usize).unwrap();
+                let base = seq.get((i + KMER - 1) as usize).unwrap();
                 let num = base2num_bytes(base);
 
                 if num < 0 {
@@ -429,7 +435,7 @@ impl Matcher {
             }
 
             let kmer_pos = self.m_kmer_positions.get(&(kmer)).unwrap();
-            for (i, gp) in kmer_pos.iter().enumerate() {
+            for gp in kmer_pos.iter() {
                 let gp_i64 = gp_to_i64(&shift(gp, i as i32));
                 kmer_stat
                     .entry(gp_i64)
@@ -483,7 +489,7 @@ impl Matcher {
                 valid = *kmer_valid.get(i as usize).unwrap();
                 let kmer = *all_kmer.get(i as usize).unwrap();
 
-                if !valid || self.m_kmer_positions.contains_key(&kmer) {
+                if !valid || !self.m_kmer_positions.contains_key(&kmer) {
                     continue;
                 }
 
@@ -688,6 +694,53 @@ impl Matcher {
         }
     }
 
+    /// contig and window start of where `mr` was aligned
+    pub(crate) fn match_region(&self, mr: &MatchResult) -> (i16, i32) {
+        let bin = mr.start_gp.position.max(0) / ALIGNABLE_REGION_BIN * ALIGNABLE_REGION_BIN;
+        (mr.start_gp.contig, bin)
+    }
+
+    pub(crate) fn match_pos_str(&self, mr: &MatchResult) -> String {
+        format!(
+            "{}:{}{}",
+            self.m_contig_names
+                .get(mr.start_gp.contig as usize)
+                .map(|n| n.as_str())
+                .unwrap_or(""),
+            mr.start_gp.position + 1,
+            if mr.reversed { "(-)" } else { "(+)" }
+        )
+    }
+
+    /// regions sorted by removed reads, descending
+    pub(crate) fn alignable_regions(
+        &self,
+        counts: HashMap<(i16, i32), usize, GFHasherBuilder>,
+    ) -> Vec<AlignableRegion> {
+        let mut regions = counts
+            .into_iter()
+            .map(|((contig, start), reads)| AlignableRegion {
+                contig: self
+                    .m_contig_names
+                    .get(contig as usize)
+                    .cloned()
+                    .unwrap_or_default(),
+                start,
+                end: start + ALIGNABLE_REGION_BIN,
+                reads,
+            })
+            .collect::<Vec<_>>();
+
+        regions.sort_by(|a, b| {
+            b.reads
+                .cmp(&a.reads)
+                .then_with(|| a.contig.cmp(&b.contig))
+                .then_with(|| a.start.cmp(&b.start))
+        });
+
+        regions
+    }
+
     fn is_consistent(&self, thisgp: i64, kmer: u32, seqpos: i32, threshold: i32) -> bool {
         let gps = self.m_kmer_positions.get(&(kmer as u32)).unwrap();
         // align by seqpos
@@ -777,19 +830,15 @@ fn make_kmer_cv(seq: &[char], pos: usize, valid: &mut bool) -> u32 {
         match base {
             'A' => {
                 kmer += 0;
-                break;
             }
             'T' => {
                 kmer += 1;
-                break;
             }
             'C' => {
                 kmer += 2;
-                break;
             }
             'G' => {
                 kmer += 3;
-                break;
             }
             _ => {
                 *valid = false;
@@ -818,19 +867,15 @@ fn make_kmer_bytes(seq: &[u8], pos: usize, valid: &mut bool) -> u32 {
         match base {
             b'A' => {
                 kmer += 0;
-                break;
             }
             b'T' => {
                 kmer += 1;
-                break;
             }
             b'C' => {
                 kmer += 2;
-                break;
             }
             b'G' => {
                 kmer += 3;
-                break;
             }
             _ => {
                 *valid = false;
@@ -854,19 +899,15 @@ fn make_kmer(seq: &str, pos: usize, valid: &mut bool) -> u32 {
         match base {
             'A' => {
                 kmer += 0;
-                break;
             }
             'T' => {
                 kmer += 1;
-                break;
             }
             'C' => {
                 kmer += 2;
-                break;
             }
             'G' => {
                 kmer += 3;
-                break;
             }
             _ => {
                 *valid = false;
@@ -908,3 +949,39 @@ pub(crate) fn i64_to_gp(val: i64) -> GenePos {
         position: (val & 0x00000000FFFFFFFF) as i32,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn kmer_of_all_bases() {
+        let mut valid = false;
+        // each base used to end the k-mer, so that all k-mers starting with A were 0
+        assert_eq!(make_kmer("AAAAAAAAAAAAAAAT", 0, &mut valid), 1);
+        assert!(valid);
+        assert_eq!(make_kmer_bytes(b"CAAAAAAAAAAAAAAG", 0, &mut valid), (2 << 30) | 3);
+        assert_eq!(make_kmer_bytes(b"NAAAAAAAAAAAAAAG", 0, &mut valid), 0);
+        assert!(!valid);
+    }
+
+    #[test]
+    fn read_placement() {
+        let mut reference = FastaReader::new("testdata/tinyref.fa", true).unwrap();
+        reference.read_all();
+        let contig2 = reference.m_all_contigs.get("contig2").unwrap().clone();
+        let read = Sequence::new(contig2[20..70].to_string());
+
+        let mut matcher = Matcher::from_ref_and_seqs(Arc::new(reference), &[read.clone()], &ExcludeRegions::default(), None);
+        assert_eq!(matcher.m_contig_names, vec!["contig1", "contig2"]);
+
+        // the mask used to skip the k-mers found in the index and look up the missing ones, and
+        // the votes were shifted by the index of each hit instead of the read offset
+        let mr = matcher.do_match(&read).unwrap();
+        assert!(mr.mismatches.is_empty());
+        assert_eq!(matcher.match_pos_str(&mr), "contig2:21(+)");
+
+        let mr = matcher.do_match(&read.reverse_complement()).unwrap();
+        assert_eq!(matcher.match_pos_str(&mr), "contig2:21(-)");
+    }
+}
@@ -1,5 +1,18 @@
+pub(crate) mod allele_fraction;
+#[doc(hidden)]
+pub mod bench;
+pub(crate) mod break_context;
+pub mod breakpoint_cluster;
+pub(crate) mod circ_rna;
 pub(crate) mod common;
+pub(crate) mod confidence;
+pub(crate) mod consensus;
+pub(crate) mod domain;
 pub(crate) mod edit_distance;
+pub(crate) mod event_type;
+pub(crate) mod exclude_region;
+pub(crate) mod exon_skip;
+pub(crate) mod exon_snap;
 pub(crate) mod fasta_reader;
 pub(crate) mod fastq_reader;
 pub(crate) mod fusion;
@@ -7,13 +20,26 @@ pub(crate) mod fusion_mapper;
 pub(crate) mod fusion_result;
 pub mod fusion_scan;
 pub(crate) mod gene;
+pub(crate) mod hotspot;
 pub(crate) mod html_reporter;
+pub(crate) mod index_stat;
 pub(crate) mod indexer;
 pub(crate) mod json_reporter;
+pub(crate) mod known_fusion;
+pub mod kmer_vote;
 pub(crate) mod matcher;
+pub(crate) mod multi_fusion_result;
 pub(crate) mod overlap;
 pub(crate) mod read;
 pub(crate) mod read_match;
+pub(crate) mod read_through;
+pub(crate) mod reciprocal;
+pub(crate) mod rejection;
 pub(crate) mod sequence;
 pub(crate) mod pescanner;
+pub(crate) mod pon;
 pub(crate) mod sescanner;
+pub(crate) mod spanning_pair;
+pub(crate) mod transcript;
+pub(crate) mod tsv_reporter;
+pub(crate) mod vcf_reporter;
@@ -0,0 +1,216 @@
+use std::fmt::Write;
+
+use crate::aux::global_settings::global_settings;
+
+use super::{
+    common::GenePos,
+    fusion::Fusion,
+    gene::Gene,
+    read_match::{ChainJunction, ReadMatch},
+};
+
+/// A rearrangement joining more than two genome segments, e.g. a short templated insertion
+/// between two fusion partners. It is only supported by reads spanning every junction.
+#[derive(Default)]
+pub(crate) struct MultiFusionResult<'s> {
+    pub(crate) m_junctions: Vec<ChainJunction>,
+    pub(crate) m_matches: Vec<ReadMatch<'s>>,
+    pub(crate) m_unique: i32,
+    pub(crate) m_title: String,
+    // gene of every segment, in read order
+    pub(crate) m_genes: Vec<Gene>,
+    // position strings of every junction
+    pub(crate) m_left_pos: Vec<String>,
+    pub(crate) m_right_pos: Vec<String>,
+}
+
+impl<'s> MultiFusionResult<'s> {
+    pub(crate) fn add_match(&mut self, m: ReadMatch<'s>) {
+        self.m_matches.push(m);
+    }
+
+    pub(crate) fn segment_count(&self) -> usize {
+        self.m_junctions.len() + 1
+    }
+
+    /// like FusionResult::calc_fusion_point(), prefer a read without gap, else use the mean of all
+    pub(crate) fn calc_junctions(&mut self) -> () {
+        let Some(first) = self.m_matches.first() else {
+            return;
+        };
+
+        if let Some(rm) = self
+            .m_matches
+            .iter()
+            .find(|rm| rm.m_junctions.iter().all(|j| j.m_gap == 0))
+        {
+            self.m_junctions = rm.m_junctions.clone();
+            return;
+        }
+
+        let n = self.m_matches.len() as i64;
+        self.m_junctions = first
+            .m_junctions
+            .iter()
+            .enumerate()
+            .map(|(i, j)| {
+                let left_total = self
+                    .m_matches
+                    .iter()
+                    .map(|rm| rm.m_junctions[i].m_left_gp.position as i64)
+                    .sum::<i64>();
+                let right_total = self
+                    .m_matches
+                    .iter()
+                    .map(|rm| rm.m_junctions[i].m_right_gp.position as i64)
+                    .sum::<i64>();
+
+                ChainJunction {
+                    m_read_break: j.m_read_break,
+                    m_left_gp: GenePos {
+                        contig: j.m_left_gp.contig,
+                        position: (left_total / n) as i32,
+                    },
+                    m_right_gp: GenePos {
+                        contig: j.m_right_gp.contig,
+                        position: (right_total / n) as i32,
+                    },
+                    m_gap: j.m_gap,
+                }
+            })
+            .collect();
+    }
+
+    pub(crate) fn calc_unique(&mut self) -> () {
+        self.m_unique = 1;
+
+        // since it is sorted, so just check every match with previous one
+        for w in self.m_matches.windows(2) {
+            if w[0].segment_breaks() != w[1].segment_breaks() || w[0].m_read.len() != w[1].m_read.len()
+            {
+                self.m_unique += 1;
+            }
+        }
+    }
+
+    pub(crate) fn unqualified_reason(&self) -> Option<String> {
+        let unique_requirement = global_settings().unique_requirement;
+
+        if self.m_unique < unique_requirement as i32 {
+            return Some(format!("unique reads {} < {}", self.m_unique, unique_requirement));
+        }
+
+        None
+    }
+
+    pub(crate) fn update_info(&mut self, fusions: &[Fusion]) -> () {
+        let gene_of = |gp: &GenePos| fusions.get(gp.contig as usize).unwrap().m_gene.clone();
+
+        self.m_genes = self
+            .m_junctions
+            .iter()
+            .map(|j| gene_of(&j.m_left_gp))
+            .chain(self.m_junctions.last().map(|j| gene_of(&j.m_right_gp)))
+            .collect();
+
+        self.m_left_pos = self
+            .m_junctions
+            .iter()
+            .zip(self.m_genes.iter())
+            .map(|(j, g)| g.pos2str(j.m_left_gp.position).unwrap())
+            .collect();
+        self.m_right_pos = self
+            .m_junctions
+            .iter()
+            .zip(self.m_genes.iter().skip(1))
+            .map(|(j, g)| g.pos2str(j.m_right_gp.position).unwrap())
+            .collect();
+
+        let mut ss = String::new();
+        write!(ss, "{}-way rearrangement: ", self.segment_count()).unwrap();
+        for (i, (l, r)) in self.m_left_pos.iter().zip(self.m_right_pos.iter()).enumerate() {
+            if i > 0 {
+                ss.push_str(" ~ ");
+            }
+            write!(ss, "{}___{}", l, r).unwrap();
+        }
+        write!(
+            ss,
+            "  (total: {}, unique:{})",
+            self.m_matches.len(),
+            self.m_unique
+        )
+        .unwrap();
+
+        self.m_title = ss;
+    }
+
+    pub(crate) fn print(&self) {
+        println!("\n#{}", self.m_title);
+        for (i, m) in self.m_matches.iter().enumerate() {
+            print!(">{}, ", i + 1);
+            m.print();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::{breakpoint_cluster::cluster_chain_breakpoints, read::SequenceRead};
+
+    fn chained_match(breaks: &[i32], positions: &[(i16, i32, i16, i32)]) -> ReadMatch<'static> {
+        let read = SequenceRead::new(
+            "@r".to_string(),
+            "A".repeat(120),
+            "+".to_string(),
+            "I".repeat(120),
+            true,
+        );
+        let junctions = breaks
+            .iter()
+            .zip(positions.iter())
+            .map(|(b, (lc, lp, rc, rp))| ChainJunction {
+                m_read_break: *b,
+                m_left_gp: GenePos {
+                    contig: *lc,
+                    position: *lp,
+                },
+                m_right_gp: GenePos {
+                    contig: *rc,
+                    position: *rp,
+                },
+                m_gap: 0,
+            })
+            .collect::<Vec<_>>();
+
+        let first = junctions.first().unwrap();
+        let mut m = ReadMatch::new(
+            read,
+            first.m_read_break,
+            first.m_left_gp.clone(),
+            first.m_right_gp.clone(),
+            0,
+            false,
+        );
+        m.m_junctions = junctions;
+        m
+    }
+
+    #[test]
+    fn support_requires_every_junction() {
+        let m1 = chained_match(&[40, 80], &[(0, 1000, 1, 500), (1, 540, 2, 2000)]);
+        let m2 = chained_match(&[30, 70], &[(0, 1002, 1, 501), (1, 539, 2, 1998)]);
+        let m3 = chained_match(&[30, 70], &[(0, 1002, 1, 501), (1, 600, 2, 1998)]);
+
+        let breaks = [m1.junction_breaks(), m2.junction_breaks(), m3.junction_breaks()];
+        assert_eq!(cluster_chain_breakpoints(&breaks), vec![0, 0, 1]);
+
+        let mut mfr = MultiFusionResult::default();
+        mfr.add_match(m1);
+        mfr.add_match(m2);
+        mfr.calc_junctions();
+        assert_eq!(mfr.segment_count(), 3);
+        assert_eq!(mfr.m_junctions[1].m_left_gp.position, 540);
+    }
+}
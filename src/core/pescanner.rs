@@ -18,7 +18,7 @@ use super::{
     fasta_reader::{self, FastaReader},
     fastq_reader::FastqReaderPair,
     fusion_mapper::FusionMapper,
-    fusion_scan::Error,
+    fusion_scan::{write_reports, Error},
     read::{SequenceReadPair, SequenceReadPairCow},
     read_match::ReadMatch,
 };
@@ -334,12 +334,14 @@ impl<'s> PairEndScanner<'s> {
         log::debug!("run matches methods...");
         m_fusion_mapper.filter_matches(self.m_thread_pool.as_ref());
         m_fusion_mapper.sort_matches();
-        m_fusion_mapper.cluster_matches();
+        m_fusion_mapper.cluster_matches(self.m_thread_pool.as_ref());
 
         log::debug!("making html reports...");
         self.html_report().unwrap();
         log::debug!("making json reports...");
         self.json_report().unwrap();
+        log::debug!("making file reports...");
+        write_reports(self.m_fusion_mapper_o.as_ref().unwrap(), &self.m_fusion_file).unwrap();
 
         let m_fusion_mapper = self.m_fusion_mapper_o.as_mut().unwrap();
         m_fusion_mapper.free_matches();
@@ -427,6 +429,9 @@ impl<'s> PairEndScanner<'s> {
     fn scan_pair_end(&self, pack: ReadPairPack<'s>) -> Result<bool, Error> {
         let m_fusion_mapper = self.m_fusion_mapper_o.as_ref().unwrap();
 
+        // reads lying in one gene, to count the reference reads at fusion breaks
+        let mut wild_type = Vec::new();
+
         for (p, pair) in (0..(pack.count as usize)).zip(pack.data.into_iter()) {
             // let pair = pack.data.get(p).unwrap();
             let r1 = &pair.m_left;
@@ -436,17 +441,28 @@ impl<'s> PairEndScanner<'s> {
             let rcr2;
 
             let merged = pair.fast_merge();
+
+            if !m_fusion_mapper.m_hotspots.is_empty() {
+                match merged {
+                    Some(ref m) => m_fusion_mapper.genotype_hotspots(&[m]),
+                    None => m_fusion_mapper.genotype_hotspots(&[r1, r2]),
+                }
+            }
             // if pair.m_left.m_name.contains(DBT) {
             //     log::debug!("merged={:#?}", merged);
             // };
 
             let mut mapable = false;
+            // gene positions of mates lying in one gene only, for spanning pairs
+            let mut located = None;
+            let mut located_r1 = None;
+            let mut located_r2 = None;
 
             let merged_rc;
             // if merged successfully, we only search the merged
             // log::debug!("p={}, merged={:?}", p, merged);
             if let Some(ref m) = merged {
-                let mut match_merged = m_fusion_mapper.map_read(m, &mut mapable, 2, 20)?;
+                let mut match_merged = m_fusion_mapper.map_read(m, &mut mapable, &mut located, 2, 20)?;
                 // if pair.m_left.m_name.contains(DBT) {
                 //     log::debug!("match_merged={:#?}", match_merged);
                 // };
@@ -458,7 +474,7 @@ impl<'s> PairEndScanner<'s> {
                 } else if mapable {
                     merged_rc = m.reverse_complement();
                     let mut match_merged_rc =
-                        m_fusion_mapper.map_read(&merged_rc, &mut mapable, 2, 20)?;
+                        m_fusion_mapper.map_read(&merged_rc, &mut mapable, &mut located, 2, 20)?;
                     // if pair.m_left.m_name.contains(DBT) {
                     //     log::debug!("match_merged_rc={:#?}", match_merged_rc);
                     // };
@@ -467,11 +483,15 @@ impl<'s> PairEndScanner<'s> {
                         self.push_match(mmr);
                     }
                 }
+                if let Some(ref gp) = located {
+                    wild_type.extend(m_fusion_mapper.wild_type_interval(m, gp));
+                }
                 continue;
             }
             // else still search R1 and R2 separatedly
             mapable = false;
-            let match_r1 = m_fusion_mapper.map_read(r1, &mut mapable, 2, 20)?;
+            let match_r1 = m_fusion_mapper.map_read(r1, &mut mapable, &mut located_r1, 2, 20)?;
+            let mut split_found = match_r1.is_some();
             // if pair.m_left.m_name.contains(DBT) {
             //     log::debug!("match_r1={:#?}", match_r1);
             // };
@@ -480,7 +500,7 @@ impl<'s> PairEndScanner<'s> {
                 self.push_match(mr1);
             } else if mapable {
                 rcr1 = r1.reverse_complement();
-                let match_rcr1 = m_fusion_mapper.map_read(&rcr1, &mut mapable, 2, 20)?;
+                let match_rcr1 = m_fusion_mapper.map_read(&rcr1, &mut mapable, &mut located, 2, 20)?;
                 // if pair.m_left.m_name.contains(DBT) {
                 //     log::debug!("match_rcr1={:#?}", match_rcr1);
                 // };
@@ -488,12 +508,14 @@ impl<'s> PairEndScanner<'s> {
                     mrc1.add_original_pair(pair.clone());
                     mrc1.set_reversed(true);
                     self.push_match(mrc1);
+                    split_found = true;
                 }
             }
 
             mapable = false;
 
-            let match_r2 = m_fusion_mapper.map_read(r2, &mut mapable, 2, 20)?;
+            let match_r2 = m_fusion_mapper.map_read(r2, &mut mapable, &mut located_r2, 2, 20)?;
+            split_found |= match_r2.is_some();
             // if pair.m_left.m_name.contains(DBT) {
             //     log::debug!("match_r2={:#?}", match_r2);
             // };
@@ -502,7 +524,7 @@ impl<'s> PairEndScanner<'s> {
                 self.push_match(mr2);
             } else if mapable {
                 rcr2 = r2.reverse_complement();
-                let match_rcr2 = m_fusion_mapper.map_read(&rcr2, &mut mapable, 2, 20)?;
+                let match_rcr2 = m_fusion_mapper.map_read(&rcr2, &mut mapable, &mut located, 2, 20)?;
                 // if pair.m_left.m_name.contains(DBT) {
                 //     log::debug!("match_rcr2={:#?}", match_rcr2);
                 // };
@@ -510,10 +532,26 @@ impl<'s> PairEndScanner<'s> {
                     mrc2.add_original_pair(pair.clone());
                     mrc2.set_reversed(true);
                     self.push_match(mrc2);
+                    split_found = true;
+                }
+            }
+
+            for (r, gp) in [(r1, &located_r1), (r2, &located_r2)] {
+                if let Some(gp) = gp {
+                    wild_type.extend(m_fusion_mapper.wild_type_interval(r, gp));
+                }
+            }
+
+            // neither mate crosses the junction, but each may lie in one partner gene
+            if !split_found {
+                if let (Some(gp1), Some(gp2)) = (located_r1, located_r2) {
+                    m_fusion_mapper.add_spanning_pair(r1, gp1, r2, gp2);
                 }
             }
         }
 
+        m_fusion_mapper.add_wild_type_reads(wild_type);
+
         Ok(true)
     }
     // #[track_caller]
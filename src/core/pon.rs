@@ -0,0 +1,410 @@
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Write},
+    iter::Peekable,
+    str::Chars,
+};
+
+use super::fusion_scan::Error;
+
+/// Chromosome positions of both breaks of a fusion, as in the JSON report, the sign being the strand.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Breakpoints {
+    pub(crate) left_chr: String,
+    pub(crate) left_pos: i32,
+    pub(crate) right_chr: String,
+    pub(crate) right_pos: i32,
+}
+
+impl Breakpoints {
+    fn near(&self, other: &Breakpoints, tolerance: i32) -> bool {
+        self.left_chr == other.left_chr
+            && self.right_chr == other.right_chr
+            && (self.left_pos < 0) == (other.left_pos < 0)
+            && (self.right_pos < 0) == (other.right_pos < 0)
+            && (self.left_pos - other.left_pos).abs() <= tolerance
+            && (self.right_pos - other.right_pos).abs() <= tolerance
+    }
+}
+
+/// A junction of the panel and the normal samples it was found in.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PonJunction {
+    pub(crate) m_breaks: Breakpoints,
+    pub(crate) m_samples: usize,
+}
+
+/// How often a call was seen in the normal samples.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct PonHit {
+    pub(crate) m_samples: usize,
+    pub(crate) m_total_samples: usize,
+}
+
+impl PonHit {
+    pub(crate) fn frequency(&self) -> f64 {
+        self.m_samples as f64 / self.m_total_samples.max(1) as f64
+    }
+}
+
+/// Junctions recurring in normal samples, artefacts of the assay rather than fusions.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct PanelOfNormals {
+    pub(crate) m_total_samples: usize,
+    pub(crate) m_junctions: Vec<PonJunction>,
+}
+
+impl PanelOfNormals {
+    /// Count, for every junction, the samples having a fusion within `tolerance` of it.
+    ///
+    /// A junction is kept at the breaks it was first seen with, a sample counts once per junction.
+    pub(crate) fn from_samples(samples: &[Vec<Breakpoints>], tolerance: i32) -> Self {
+        let mut pon = Self {
+            m_total_samples: samples.len(),
+            m_junctions: Vec::new(),
+        };
+
+        for breaks in samples.iter() {
+            let mut seen = vec![false; pon.m_junctions.len()];
+
+            for bp in breaks.iter() {
+                match pon.m_junctions.iter().position(|j| j.m_breaks.near(bp, tolerance)) {
+                    Some(j) if seen[j] => {}
+                    Some(j) => {
+                        seen[j] = true;
+                        pon.m_junctions[j].m_samples += 1;
+                    }
+                    None => {
+                        pon.m_junctions.push(PonJunction {
+                            m_breaks: bp.clone(),
+                            m_samples: 1,
+                        });
+                        seen.push(true);
+                    }
+                }
+            }
+        }
+
+        pon
+    }
+
+    /// `json_files` are genefuse JSON reports of normal samples
+    pub(crate) fn from_json_files(json_files: &[String], tolerance: i32) -> Result<Self, Error> {
+        let samples = json_files
+            .iter()
+            .map(|file| {
+                breakpoints_of_report(&fs::read_to_string(file)?)
+                    .map_err(|e| format!("{}: {}", file, e).into())
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self::from_samples(&samples, tolerance))
+    }
+
+    /// the junction found in most normal samples within `tolerance` of `bp`
+    pub(crate) fn find(&self, bp: &Breakpoints, tolerance: i32) -> Option<PonHit> {
+        self.m_junctions
+            .iter()
+            .filter(|j| j.m_breaks.near(bp, tolerance))
+            .map(|j| j.m_samples)
+            .max()
+            .map(|m_samples| PonHit {
+                m_samples,
+                m_total_samples: self.m_total_samples,
+            })
+    }
+
+    /// TSV with the sample count in the header, junctions found in less than `min_samples` are left out
+    pub(crate) fn write(&self, file: &str, min_samples: usize) -> Result<(), Error> {
+        let mut f = BufWriter::new(File::create(file)?);
+
+        writeln!(f, "#samples\t{}", self.m_total_samples)?;
+        writeln!(f, "#left_chr\tleft_position\tright_chr\tright_position\tsamples\tfrequency")?;
+        for j in self.m_junctions.iter().filter(|j| j.m_samples >= min_samples) {
+            let bp = &j.m_breaks;
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}\t{:.4}",
+                bp.left_chr,
+                bp.left_pos,
+                bp.right_chr,
+                bp.right_pos,
+                j.m_samples,
+                j.m_samples as f64 / self.m_total_samples.max(1) as f64
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn read(file: &str) -> Result<Self, Error> {
+        let reader = BufReader::new(File::open(file)?);
+        let mut pon = Self::default();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+
+            if let Some(samples) = line.strip_prefix("#samples\t") {
+                pon.m_total_samples = samples.trim().parse()?;
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let cols = line.split('\t').collect::<Vec<_>>();
+            if cols.len() < 5 {
+                return Err(format!(
+                    "{}:{}: a panel of normals line needs left_chr, left_position, right_chr, right_position and samples columns",
+                    file,
+                    i + 1
+                )
+                .into());
+            }
+
+            pon.m_junctions.push(PonJunction {
+                m_breaks: Breakpoints {
+                    left_chr: cols[0].to_owned(),
+                    left_pos: cols[1].parse()?,
+                    right_chr: cols[2].to_owned(),
+                    right_pos: cols[3].parse()?,
+                },
+                m_samples: cols[4].parse()?,
+            });
+        }
+
+        if pon.m_total_samples == 0 {
+            return Err(format!("{}: the '#samples' header is missing", file).into());
+        }
+
+        Ok(pon)
+    }
+}
+
+/// breaks of every fusion in a JSON report.
+///
+/// Only genefuse's own reports are meant to be read. The small parser below, used instead of a
+/// JSON library, accepts what json_reporter writes and is tested on one of its reports.
+fn breakpoints_of_report(json: &str) -> Result<Vec<Breakpoints>, Error> {
+    let report = JsonValue::parse(json)?;
+
+    let fusions = match report.get("fusions") {
+        Some(JsonValue::Object(fusions)) => fusions,
+        _ => return Err("no 'fusions' object".into()),
+    };
+
+    fusions
+        .iter()
+        .map(|(title, fusion)| {
+            let side = |name: &str| {
+                let s = fusion.get(name)?;
+                match (s.get("gene_chr")?, s.get("position")?) {
+                    (JsonValue::String(chr), JsonValue::Number(pos)) => Some((chr.clone(), *pos as i32)),
+                    _ => None,
+                }
+            };
+
+            match (side("left"), side("right")) {
+                (Some((left_chr, left_pos)), Some((right_chr, right_pos))) => Ok(Breakpoints {
+                    left_chr,
+                    left_pos,
+                    right_chr,
+                    right_pos,
+                }),
+                _ => Err(format!("fusion '{}' has no left and right gene_chr and position", title).into()),
+            }
+        })
+        .collect()
+}
+
+// just enough JSON to read the reports back
+#[derive(Debug, Clone, PartialEq)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    fn parse(s: &str) -> Result<Self, Error> {
+        let mut chars = s.chars().peekable();
+        let value = Self::parse_value(&mut chars)?;
+
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected '{}' after the JSON value", c).into()),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn parse_value(chars: &mut Peekable<Chars>) -> Result<Self, Error> {
+        skip_whitespace(chars);
+
+        match chars.peek() {
+            Some('{') => {
+                chars.next();
+                let mut members = Vec::new();
+                loop {
+                    skip_whitespace(chars);
+                    match chars.next() {
+                        Some('}') if members.is_empty() => break,
+                        Some('"') => {}
+                        _ => return Err("an object key is expected".into()),
+                    }
+                    let key = parse_string(chars)?;
+                    skip_whitespace(chars);
+                    if chars.next() != Some(':') {
+                        return Err(format!("':' is expected after '{}'", key).into());
+                    }
+                    members.push((key, Self::parse_value(chars)?));
+                    skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => {}
+                        Some('}') => break,
+                        _ => return Err("',' or '}' is expected in an object".into()),
+                    }
+                }
+                Ok(JsonValue::Object(members))
+            }
+            Some('[') => {
+                chars.next();
+                let mut items = Vec::new();
+                skip_whitespace(chars);
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                    return Ok(JsonValue::Array(items));
+                }
+                loop {
+                    items.push(Self::parse_value(chars)?);
+                    skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => {}
+                        Some(']') => break,
+                        _ => return Err("',' or ']' is expected in an array".into()),
+                    }
+                }
+                Ok(JsonValue::Array(items))
+            }
+            Some('"') => {
+                chars.next();
+                Ok(JsonValue::String(parse_string(chars)?))
+            }
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, ',' | '}' | ']') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                match word.as_str() {
+                    "null" => Ok(JsonValue::Null),
+                    "true" => Ok(JsonValue::Bool(true)),
+                    "false" => Ok(JsonValue::Bool(false)),
+                    _ => Ok(JsonValue::Number(
+                        word.parse().map_err(|_| format!("'{}' is not a JSON value", word))?,
+                    )),
+                }
+            }
+            None => Err("unexpected end of JSON".into()),
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().map_or(false, |c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+// after the opening quote
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, Error> {
+    let mut s = String::new();
+
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(s),
+            Some('\\') => match chars.next() {
+                Some('n') => s.push('\n'),
+                Some('t') => s.push('\t'),
+                Some('r') => s.push('\r'),
+                Some('u') => {
+                    let hex = chars.by_ref().take(4).collect::<String>();
+                    s.push(
+                        u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .unwrap_or(char::REPLACEMENT_CHARACTER),
+                    );
+                }
+                Some(c) => s.push(c),
+                None => return Err("unexpected end of JSON in a string".into()),
+            },
+            Some(c) => s.push(c),
+            None => return Err("unexpected end of JSON in a string".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bp(left_pos: i32, right_pos: i32) -> Breakpoints {
+        Breakpoints {
+            left_chr: "chr2".to_owned(),
+            left_pos,
+            right_chr: "chr12".to_owned(),
+            right_pos,
+        }
+    }
+
+    #[test]
+    fn recurrent_junctions() {
+        let report = r##"{
+            "command":"genefuse -1 n1.fq",
+            "thresholds":{"unique":2, "collapse_reciprocal":false},
+            "fusions":{
+                "Fusion: A_B (total: 3, unique:3)":{
+                    "left":{"gene_name":"A", "gene_chr":"chr2", "position":1000, "exon_or_intron_id":-1},
+                    "right":{"gene_name":"B", "gene_chr":"chr12", "position":-5000},
+                    "vaf_ci":[0.1, 0.5],
+                    "consensus":{"qual":"II\"#"}
+                }
+            }
+        }"##;
+        assert_eq!(breakpoints_of_report(report).unwrap(), vec![bp(1000, -5000)]);
+
+        // a report written by json_reporter
+        let report = fs::read_to_string("testdata/normal_report.json").unwrap();
+        let breaks = breakpoints_of_report(&report).unwrap();
+        let positions = breaks.iter().map(|b| (b.left_chr.as_str(), b.left_pos, b.right_chr.as_str(), b.right_pos)).collect::<Vec<_>>();
+        assert_eq!(positions, vec![("chrA", 18158, "chrC", 32008), ("chrA", 18154, "chrC", 32004)]);
+
+        let samples = vec![
+            vec![bp(1000, -5000), bp(1002, -5001)],
+            vec![bp(1003, -4998)],
+            vec![bp(1000, 5000), bp(2000, -6000)],
+        ];
+        let pon = PanelOfNormals::from_samples(&samples, 5);
+
+        assert_eq!(pon.m_junctions.len(), 3);
+        assert_eq!(pon.m_junctions[0].m_samples, 2);
+
+        let hit = pon.find(&bp(996, -5004), 5).unwrap();
+        assert_eq!((hit.m_samples, hit.m_total_samples), (2, 3));
+        assert!(pon.find(&bp(990, -5000), 5).is_none());
+    }
+}
@@ -272,7 +272,7 @@ impl SequenceRead {
     }
 }
 
-fn quality_color(qual: char) -> &'static str {
+pub(crate) fn quality_color(qual: char) -> &'static str {
     if qual >= 'I' {
         // >= Q40, extremely high quality
         return "#78C6B9";
@@ -14,6 +14,15 @@ use super::{
     read::{SequenceRead, SequenceReadCow, SequenceReadPair, SequenceReadPairCow},
 };
 
+/// One junction of a read whose sequence chains more than two genome segments.
+#[derive(PartialEq, Clone, Debug)]
+pub(crate) struct ChainJunction {
+    pub(crate) m_read_break: i32,
+    pub(crate) m_left_gp: GenePos,
+    pub(crate) m_right_gp: GenePos,
+    pub(crate) m_gap: i32,
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub(crate) struct ReadMatch<'s> {
     pub(crate) m_read: SequenceRead,
@@ -27,6 +36,11 @@ pub(crate) struct ReadMatch<'s> {
     pub(crate) m_read_break: i32,
     pub(crate) m_left_gp: GenePos,
     pub(crate) m_right_gp: GenePos,
+    // all junctions in read order when the read chains more than two segments, empty otherwise.
+    // the first junction is the same as m_read_break, m_left_gp and m_right_gp.
+    pub(crate) m_junctions: Vec<ChainJunction>,
+    // edit distance of every segment of a chained read
+    pub(crate) m_segment_distances: Vec<i32>,
 }
 
 impl<'s> ReadMatch<'s> {
@@ -50,9 +64,32 @@ impl<'s> ReadMatch<'s> {
             m_read_break: read_break,
             m_left_gp: left_gp,
             m_right_gp: right_gp,
+            m_junctions: Vec::new(),
+            m_segment_distances: Vec::new(),
+        }
+    }
+
+    pub(crate) fn is_multi_junction(&self) -> bool {
+        self.m_junctions.len() > 1
+    }
+
+    /// read coordinates where each segment after the first one starts
+    pub(crate) fn segment_breaks(&self) -> Vec<i32> {
+        if self.is_multi_junction() {
+            self.m_junctions.iter().map(|j| j.m_read_break + 1).collect()
+        } else {
+            vec![self.m_read_break + 1]
         }
     }
 
+    /// gene positions joined at each junction of a chained read, to cluster it with others
+    pub(crate) fn junction_breaks(&self) -> Vec<(i32, i32)> {
+        self.m_junctions
+            .iter()
+            .map(|j| (j.m_left_gp.position, j.m_right_gp.position))
+            .collect()
+    }
+
     pub(crate) fn less(m1: &ReadMatch, m2: &ReadMatch) -> bool {
         m1 < m2
     }
@@ -99,15 +136,25 @@ impl<'s> ReadMatch<'s> {
         }
 
         write!(f, "</a></span>")?;
-        write!(
-            f,
-            "</td><td>{}|{}</td>",
-            self.m_left_distance, self.m_right_distance
-        )?;
+        if self.is_multi_junction() {
+            write!(
+                f,
+                "</td><td>{}</td>",
+                self.m_segment_distances
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join("|")
+            )?;
+        } else {
+            write!(
+                f,
+                "</td><td>{}|{}</td>",
+                self.m_left_distance, self.m_right_distance
+            )?;
+        }
 
-        let mut breaks = Vec::new();
-        breaks.push(self.m_read_break + 1);
-        self.m_read.print_html_td_with_breaks(f, breaks)?;
+        self.m_read.print_html_td_with_breaks(f, self.segment_breaks())?;
 
         Ok(())
     }
@@ -131,6 +178,11 @@ impl<'s> ReadMatch<'s> {
     }
 
     pub(crate) fn print(&self) {
+        if self.is_multi_junction() {
+            self.print_chain();
+            return;
+        }
+
         print!("break:{}", self.m_read_break + 1);
         print!(
             ", diff:({} {})",
@@ -165,6 +217,47 @@ impl<'s> ReadMatch<'s> {
         );
         print!("\n");
     }
+
+    fn print_chain(&self) {
+        let breaks = self.segment_breaks();
+        print!(
+            "breaks:{}",
+            breaks
+                .iter()
+                .map(|b| b.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        print!(
+            ", diff:({})",
+            self.m_segment_distances
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+
+        if self.m_reversed {
+            print!(", read direction: reversed complement");
+        } else {
+            print!(", read direction: original direction");
+        }
+
+        print!(
+            ", name: {}",
+            self.m_read.m_name.subchars(1, self.m_read.m_name.len() - 1)
+        );
+        print!("\n");
+
+        let seq = self.m_read.m_seq.m_str.as_str();
+        let mut start = 0;
+        for b in breaks.iter() {
+            print!("{} ", seq.subchars(start, *b as usize - start));
+            start = *b as usize;
+        }
+        print!("{}", seq.subchars(start, seq.len() - start));
+        print!("\n");
+    }
 }
 
 impl<'s> fmt::Display for ReadMatch<'s> {
@@ -0,0 +1,68 @@
+use super::{fusion::Fusion, gene::Gene};
+
+/// Bases between `five` and `three` when `three` is the next gene of the list downstream of `five`
+/// on the same strand, at most `max_distance` away, so that transcription running on past the
+/// end of `five` into `three` would make this fusion. Overlapping genes are 0 apart.
+pub(crate) fn read_through_gap(five: &Gene, three: &Gene, fusion_list: &[Fusion], max_distance: i32) -> Option<i32> {
+    if five.m_name == three.m_name || five.m_chr != three.m_chr || five.is_reversed() != three.is_reversed() {
+        return None;
+    }
+
+    // first and last positions of a gene along the strand of the 5' gene
+    let span = |gene: &Gene| {
+        let (a, b) = if five.is_reversed() { (-gene.m_start, -gene.m_end) } else { (gene.m_start, gene.m_end) };
+        (a.min(b), a.max(b))
+    };
+    let (five_start, five_end) = span(five);
+    let (three_start, _) = span(three);
+    if three_start <= five_start {
+        return None;
+    }
+    let gap = (three_start - five_end - 1).max(0);
+    if gap > max_distance {
+        return None;
+    }
+
+    let between = fusion_list.iter().map(|f| &f.m_gene).any(|g| {
+        let (start, end) = span(g);
+        g.m_chr == five.m_chr && g.is_reversed() == five.is_reversed() && five_end < start && end < three_start
+    });
+
+    (!between).then_some(gap)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gene(name: &str, chr: &str, exons: &[(i32, i32)]) -> Gene {
+        let mut gene = Gene::new(name.to_string(), chr.to_string(), exons[0].0.min(exons[exons.len() - 1].0), 0);
+        gene.m_end = exons.iter().map(|e| e.1).max().unwrap();
+        for (i, &(s, e)) in exons.iter().enumerate() {
+            gene.add_exon(i as i32 + 1, s, e);
+        }
+        gene
+    }
+
+    #[test]
+    fn neighbouring_genes() {
+        let a = gene("A", "chr1", &[(1000, 1100), (1500, 2000)]);
+        let b = gene("B", "chr1", &[(5000, 5100), (5500, 6000)]);
+        let c = gene("C", "chr1", &[(9000, 9100), (9500, 9999)]);
+        let list = [Fusion::new(a.clone()), Fusion::new(b.clone()), Fusion::new(c.clone())];
+
+        assert_eq!(read_through_gap(&a, &b, &list, 10000), Some(2999));
+        assert_eq!(read_through_gap(&a, &b, &list, 1000), None);
+        // B is in between, and A is not downstream of B
+        assert_eq!(read_through_gap(&a, &c, &list, 10000), None);
+        assert_eq!(read_through_gap(&b, &a, &list, 10000), None);
+
+        // reverse genes are read from the end of the chromosome
+        let d = gene("D", "chr2", &[(8000, 8500), (7000, 7100)]);
+        let e = gene("E", "chr2", &[(3000, 3500), (2000, 2100)]);
+        assert!(d.is_reversed() && e.is_reversed());
+        assert_eq!(read_through_gap(&d, &e, &[], 10000), Some(3499));
+        assert_eq!(read_through_gap(&e, &d, &[], 10000), None);
+        assert_eq!(read_through_gap(&a, &e, &[], 10000), None);
+    }
+}
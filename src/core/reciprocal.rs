@@ -0,0 +1,228 @@
+use std::mem;
+
+use super::{common::GenePos, fusion_result::FusionResult};
+
+/// The other junction of a balanced event, like the two derivatives of a translocation.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ReciprocalPartner {
+    // FusionResult::id() of the partner
+    pub(crate) m_id: String,
+    pub(crate) m_unique: i32,
+    pub(crate) m_total: usize,
+    // the reads of the partner were merged into this result, it is not reported by itself
+    pub(crate) m_collapsed: bool,
+}
+
+fn near(gp: &GenePos, contig: i16, position: i32, tolerance: i32) -> Option<i32> {
+    // the sign of a position is the strand
+    if gp.contig != contig || (gp.position < 0) != (position < 0) {
+        return None;
+    }
+
+    let d = (gp.position - position).abs();
+    (d <= tolerance).then_some(d)
+}
+
+/// Distance between the breaks of `b` and those expected for the reciprocal of `a`, `None` if
+/// `b` is not a reciprocal within `tolerance`.
+///
+/// If `a` joins gene A up to `p` with gene B from `q`, the reciprocal joins B up to `q - 1`
+/// with A from `p + 1`. Positions increase along the fusion on either strand, so it's the
+/// same for both strands, and the reciprocal may be reported as its reverse complement too.
+pub(crate) fn reciprocal_distance(
+    a_left: &GenePos,
+    a_right: &GenePos,
+    b_left: &GenePos,
+    b_right: &GenePos,
+    tolerance: i32,
+) -> Option<i32> {
+    let (p, q) = (a_left.position, a_right.position);
+
+    let forward = near(b_left, a_right.contig, q - 1, tolerance)
+        .zip(near(b_right, a_left.contig, p + 1, tolerance));
+    let reversed = near(b_left, a_left.contig, -(p + 1), tolerance)
+        .zip(near(b_right, a_right.contig, -(q - 1), tolerance));
+
+    forward
+        .into_iter()
+        .chain(reversed)
+        .map(|(d1, d2)| d1 + d2)
+        .min()
+}
+
+/// The closest reciprocal of each result, results already given a partner are left out.
+fn find_partners(results: &[FusionResult], tolerance: i32) -> Vec<Option<usize>> {
+    let mut partner: Vec<Option<usize>> = vec![None; results.len()];
+    let free = |partner: &[Option<usize>], i: usize| partner[i].is_none() && results[i].m_reciprocal.is_none();
+
+    for i in 0..results.len() {
+        if !free(&partner, i) {
+            continue;
+        }
+
+        let best = ((i + 1)..results.len())
+            .filter(|&j| free(&partner, j))
+            .filter_map(|j| {
+                reciprocal_distance(
+                    &results[i].m_left_gp,
+                    &results[i].m_right_gp,
+                    &results[j].m_left_gp,
+                    &results[j].m_right_gp,
+                    tolerance,
+                )
+                .map(|d| (d, j))
+            })
+            .min();
+
+        if let Some((_, j)) = best {
+            partner[i] = Some(j);
+            partner[j] = Some(i);
+        }
+    }
+
+    partner
+}
+
+/// Merge every reciprocal pair into one result, the one with more unique reads, then more
+/// reads, keeps its junction.
+///
+/// The reads of the partner are moved into it and their unique ones added, so the title,
+/// confidence and allele fraction are to be computed after this.
+pub(crate) fn collapse_reciprocals(results: &mut Vec<FusionResult>, tolerance: i32) {
+    let partner = find_partners(results, tolerance);
+
+    let support = |fr: &FusionResult| (fr.m_unique, fr.m_matches.len());
+    let merged = (0..results.len())
+        .map(|i| partner[i].is_some_and(|j| (support(&results[j]), i) > (support(&results[i]), j)))
+        .collect::<Vec<_>>();
+
+    for i in (0..results.len()).filter(|&i| merged[i]) {
+        let j = partner[i].unwrap();
+        let rp = ReciprocalPartner {
+            m_id: results[i].id(),
+            m_unique: results[i].m_unique,
+            m_total: results[i].m_matches.len(),
+            m_collapsed: true,
+        };
+        let matches = mem::take(&mut results[i].m_matches);
+
+        let kept = &mut results[j];
+        kept.m_matches.extend(matches);
+        kept.m_unique += rp.m_unique;
+        kept.m_reciprocal = Some(rp);
+    }
+
+    let mut i = 0;
+    results.retain(|_| {
+        i += 1;
+        !merged[i - 1]
+    });
+}
+
+/// Give every result an event number, shared by reciprocal partners.
+///
+/// Results are expected in reporting order. A result collapsed with its partner by
+/// `collapse_reciprocals()` is an event of its own.
+pub(crate) fn pair_reciprocals(results: &mut [FusionResult], tolerance: i32) {
+    let partner = find_partners(results, tolerance);
+
+    let mut event = 0;
+    let mut events = vec![0; results.len()];
+    for i in 0..results.len() {
+        match partner[i] {
+            Some(j) if j < i => events[i] = events[j],
+            _ => {
+                event += 1;
+                events[i] = event;
+            }
+        }
+    }
+
+    let partners = (0..results.len())
+        .map(|i| {
+            partner[i].map(|j| ReciprocalPartner {
+                m_id: results[j].id(),
+                m_unique: results[j].m_unique,
+                m_total: results[j].m_matches.len(),
+                m_collapsed: false,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for (i, (fr, rp)) in results.iter_mut().zip(partners).enumerate() {
+        fr.m_event_id = events[i];
+        if rp.is_some() {
+            fr.m_reciprocal = rp;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::{read::SequenceRead, read_match::ReadMatch};
+
+    use super::*;
+
+    fn gp(contig: i16, position: i32) -> GenePos {
+        GenePos { contig, position }
+    }
+
+    fn result(left: GenePos, right: GenePos, reads: usize) -> FusionResult<'static> {
+        let mut fr = FusionResult::with_minimum();
+        for r in 0..reads {
+            let read = SequenceRead::new(format!("@r{}", r), "ACGT".to_owned(), "+".to_owned(), "IIII".to_owned(), true);
+            fr.add_match(ReadMatch::new(read, r as i32, left.clone(), right.clone(), 0, false));
+        }
+        fr.m_left_gp = left;
+        fr.m_right_gp = right;
+        fr.m_unique = reads as i32;
+
+        fr
+    }
+
+    #[test]
+    fn reciprocal_breaks() {
+        // A:+1000 joined to B:+5000, B:+4999 joined to A:+1001 is the other derivative
+        let (a_left, a_right) = (gp(0, 1000), gp(1, 5000));
+
+        assert_eq!(reciprocal_distance(&a_left, &a_right, &gp(1, 4999), &gp(0, 1001), 10), Some(0));
+        // a small deletion at one break
+        assert_eq!(reciprocal_distance(&a_left, &a_right, &gp(1, 4995), &gp(0, 1004), 10), Some(7));
+        // reported as the reverse complement
+        assert_eq!(reciprocal_distance(&a_left, &a_right, &gp(0, -1001), &gp(1, -4999), 10), Some(0));
+        // the same junction again is not a reciprocal
+        assert_eq!(reciprocal_distance(&a_left, &a_right, &a_left, &a_right, 10), None);
+        assert_eq!(reciprocal_distance(&a_left, &a_right, &gp(1, 4899), &gp(0, 1001), 10), None);
+        // opposite strand
+        assert_eq!(reciprocal_distance(&a_left, &a_right, &gp(1, -4999), &gp(0, 1001), 10), None);
+    }
+
+    #[test]
+    fn collapse_into_stronger_partner() {
+        // the weaker derivative comes first, then an unrelated fusion and the stronger one
+        let mut results = vec![
+            result(gp(1, 4999), gp(0, 1001), 2),
+            result(gp(2, 300), gp(3, 700), 4),
+            result(gp(0, 1000), gp(1, 5000), 6),
+        ];
+        let weak_id = results[0].id();
+
+        collapse_reciprocals(&mut results, 10);
+
+        assert_eq!(results.len(), 2);
+        let kept = &results[1];
+        assert_eq!((kept.m_left_gp.position, kept.m_right_gp.position), (1000, 5000));
+        assert_eq!(kept.m_matches.len(), 8);
+        assert_eq!(kept.m_unique, 8);
+        assert_eq!(
+            kept.m_reciprocal,
+            Some(ReciprocalPartner { m_id: weak_id, m_unique: 2, m_total: 2, m_collapsed: true })
+        );
+
+        // collapsed results are events of their own, not paired again
+        pair_reciprocals(&mut results, 10);
+        assert_eq!(results.iter().map(|fr| fr.m_event_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(results[0].m_reciprocal.is_none());
+        assert!(results[1].m_reciprocal.as_ref().unwrap().m_collapsed);
+    }
+}
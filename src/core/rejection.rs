@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::aux::global_settings::global_settings;
+
+use super::{fusion_scan::Error, read::SequenceRead};
+
+/// Where a candidate read was dropped, in the order the filters run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum FilterStage {
+    LowComplexity,
+    Distance,
+    Indel,
+    Alignable,
+    Unqualified,
+    NotOutput,
+}
+
+impl FilterStage {
+    pub(crate) const ALL: [FilterStage; 6] = [
+        FilterStage::LowComplexity,
+        FilterStage::Distance,
+        FilterStage::Indel,
+        FilterStage::Alignable,
+        FilterStage::Unqualified,
+        FilterStage::NotOutput,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            FilterStage::LowComplexity => "low_complexity",
+            FilterStage::Distance => "distance",
+            FilterStage::Indel => "indel",
+            FilterStage::Alignable => "alignable",
+            FilterStage::Unqualified => "unqualified",
+            FilterStage::NotOutput => "not_output",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Rejection {
+    pub(crate) stage: FilterStage,
+    pub(crate) reason: String,
+    // the fusion the read was a candidate for, e.g. `GA:+chrA:18158___GC:+chrC:32008`
+    pub(crate) candidate: String,
+    pub(crate) read: SequenceRead,
+}
+
+/// Reads dropped by the filters, counted per stage. The reads themselves are kept only when
+/// they are written to `--rejected_reads`.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Rejections {
+    pub(crate) m_counts: HashMap<FilterStage, usize>,
+    pub(crate) m_reads: Vec<Rejection>,
+    m_keep_reads: bool,
+}
+
+impl Rejections {
+    pub(crate) fn new(keep_reads: bool) -> Self {
+        Self {
+            m_keep_reads: keep_reads,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn from_global_settings() -> Self {
+        Self::new(!global_settings().rejected_reads_file.is_empty())
+    }
+
+    /// counts a read dropped at `stage`, `rejection` is only made when the reads are kept
+    pub(crate) fn add(&mut self, stage: FilterStage, rejection: impl FnOnce() -> Rejection) {
+        *self.m_counts.entry(stage).or_insert(0) += 1;
+        if self.m_keep_reads {
+            self.m_reads.push(rejection());
+        }
+    }
+
+    pub(crate) fn extend(&mut self, other: Rejections) {
+        for (stage, count) in other.m_counts.into_iter() {
+            *self.m_counts.entry(stage).or_insert(0) += count;
+        }
+        self.m_reads.extend(other.m_reads);
+    }
+
+    pub(crate) fn count(&self, stage: FilterStage) -> usize {
+        self.m_counts.get(&stage).copied().unwrap_or(0)
+    }
+
+    /// reads dropped at any stage
+    pub(crate) fn len(&self) -> usize {
+        self.m_counts.values().sum()
+    }
+}
+
+/// One row of the filter funnel, reads removed at a stage and reads left after it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FunnelRow {
+    pub(crate) stage: &'static str,
+    pub(crate) removed: usize,
+    pub(crate) remaining: usize,
+}
+
+pub(crate) fn filter_funnel(candidates: usize, rejections: &Rejections) -> Vec<FunnelRow> {
+    let mut remaining = candidates;
+
+    FilterStage::ALL
+        .iter()
+        .map(|stage| {
+            let removed = rejections.count(*stage);
+            remaining = remaining.saturating_sub(removed);
+
+            FunnelRow {
+                stage: stage.name(),
+                removed,
+                remaining,
+            }
+        })
+        .collect()
+}
+
+/// FASTQ when the file name ends with `.fq` or `.fastq`, TSV otherwise.
+pub(crate) fn write_rejected_reads(file: &str, rejections: &[Rejection]) -> Result<(), Error> {
+    let mut f = BufWriter::new(File::create(file)?);
+
+    let is_fastq = matches!(
+        Path::new(file).extension().and_then(|e| e.to_str()),
+        Some("fq") | Some("fastq")
+    );
+
+    if is_fastq {
+        for rj in rejections.iter() {
+            writeln!(
+                f,
+                "{} stage={} reason={} candidate={}",
+                rj.read.m_name,
+                rj.stage.name(),
+                rj.reason,
+                rj.candidate
+            )?;
+            writeln!(f, "{}", rj.read.m_seq.m_str)?;
+            writeln!(f, "+")?;
+            writeln!(f, "{}", rj.read.m_quality)?;
+        }
+    } else {
+        writeln!(f, "read\tstage\treason\tcandidate\tseq\tqual")?;
+        for rj in rejections.iter() {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                rj.read.m_name.trim_start_matches('@'),
+                rj.stage.name(),
+                rj.reason,
+                rj.candidate,
+                rj.read.m_seq.m_str,
+                rj.read.m_quality
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn funnel_counts_down() {
+        let read = SequenceRead::new(
+            "@r1".to_owned(),
+            "ACGT".to_owned(),
+            "+".to_owned(),
+            "IIII".to_owned(),
+            true,
+        );
+        let (mut counted, mut kept) = (Rejections::new(false), Rejections::new(true));
+        for stage in [FilterStage::Distance, FilterStage::Alignable, FilterStage::Distance] {
+            let rejection = || Rejection {
+                stage,
+                reason: String::new(),
+                candidate: String::new(),
+                read: read.clone(),
+            };
+            counted.add(stage, rejection);
+            kept.add(stage, rejection);
+        }
+        assert!(counted.m_reads.is_empty());
+        assert_eq!(kept.m_reads.len(), 3);
+
+        let mut rejections = Rejections::new(false);
+        rejections.extend(counted);
+        assert_eq!(rejections.len(), 3);
+        let funnel = filter_funnel(10, &rejections);
+
+        assert_eq!(funnel.len(), FilterStage::ALL.len());
+        assert_eq!(
+            funnel.iter().map(|r| (r.removed, r.remaining)).collect::<Vec<_>>(),
+            vec![(0, 10), (2, 8), (0, 8), (1, 7), (0, 7), (0, 7)]
+        );
+    }
+}
@@ -20,7 +20,7 @@ use crate::core::{
 use super::{
     fasta_reader::FastaReader,
     fusion_mapper::FusionMapper,
-    fusion_scan::Error,
+    fusion_scan::{write_reports, Error},
     html_reporter::HtmlReporter,
     json_reporter::JsonReporter,
     read::{SequenceRead, SequenceReadCow},
@@ -150,12 +150,14 @@ impl<'s> SingleEndScanner<'s> {
         log::debug!("run matches methods...");
         m_fusion_mapper.filter_matches(self.m_thread_pool.as_ref());
         m_fusion_mapper.sort_matches();
-        m_fusion_mapper.cluster_matches();
+        m_fusion_mapper.cluster_matches(self.m_thread_pool.as_ref());
 
         log::debug!("making html reports...");
         self.html_report().unwrap();
         log::debug!("making json reports...");
         self.json_report().unwrap();
+        log::debug!("making file reports...");
+        write_reports(self.m_fusion_mapper_o.as_ref().unwrap(), &self.m_fusion_file).unwrap();
 
         let m_fusion_mapper = self.m_fusion_mapper_o.as_mut().unwrap();
         m_fusion_mapper.free_matches();
@@ -183,24 +185,36 @@ impl<'s> SingleEndScanner<'s> {
     fn scan_single_end(&self, pack: ReadPack<'s>) -> Result<bool, Error> {
         let m_fusion_mapper = self.m_fusion_mapper_o.as_ref().unwrap();
 
+        // reads lying in one gene, to count the reference reads at fusion breaks
+        let mut wild_type = Vec::new();
+
         for (p, r1) in (0..(pack.count as usize)).zip(pack.data.into_iter()) {
+            if !m_fusion_mapper.m_hotspots.is_empty() {
+                m_fusion_mapper.genotype_hotspots(&[&r1]);
+            }
+
             let mut mapable = false;
-            let match_r1 = m_fusion_mapper.map_read(&r1, &mut mapable, 2, 20)?;
+            let mut located = None;
+            let match_r1 = m_fusion_mapper.map_read(&r1, &mut mapable, &mut located, 2, 20)?;
 
             if let Some(mut mr1) = match_r1 {
                 mr1.add_original_read(r1);
                 self.push_match(mr1);
             } else if mapable {
                 let rcr1 = r1.reverse_complement();
-                let match_rcr1 = m_fusion_mapper.map_read(&rcr1, &mut mapable, 2, 20)?;
+                let match_rcr1 = m_fusion_mapper.map_read(&rcr1, &mut mapable, &mut located, 2, 20)?;
                 if let Some(mut mrcr1) = match_rcr1 {
                     mrcr1.add_original_read(r1);
                     mrcr1.set_reversed(true);
                     self.push_match(mrcr1);
                 }
+            } else if let Some(ref gp) = located {
+                wild_type.extend(m_fusion_mapper.wild_type_interval(&r1, gp));
             }
         }
 
+        m_fusion_mapper.add_wild_type_reads(wild_type);
+
         Ok(true)
     }
 
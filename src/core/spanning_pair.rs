@@ -0,0 +1,123 @@
+use super::common::GenePos;
+
+// a mate may run this far over the junction and still be mapped to a single gene only
+const JUNCTION_OVERLAP_TOLERANCE: i32 = 20;
+
+/// A read pair with one mate wholly in each partner gene.
+///
+/// Both mates are put in the orientation of the fusion, like the two parts of a split read,
+/// so positions increase from the left mate towards the junction and on into the right mate.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SpanningPair {
+    pub(crate) m_name: String,
+    // gene position of the first base of each mate
+    pub(crate) m_left_gp: GenePos,
+    pub(crate) m_left_len: i32,
+    pub(crate) m_right_gp: GenePos,
+    pub(crate) m_right_len: i32,
+}
+
+impl SpanningPair {
+    /// insert size if the fragment comes from a junction at `left_break`/`right_break`,
+    /// `None` if the mates are on the wrong genes or at the wrong sides of the junction.
+    pub(crate) fn insert_size(&self, left_break: &GenePos, right_break: &GenePos) -> Option<i32> {
+        if self.m_left_gp.contig != left_break.contig || self.m_right_gp.contig != right_break.contig
+        {
+            return None;
+        }
+
+        // positions of both mates and breaks are signed the same way, the same strand is required
+        if (self.m_left_gp.position < 0) != (left_break.position < 0)
+            || (self.m_right_gp.position < 0) != (right_break.position < 0)
+        {
+            return None;
+        }
+
+        let left_end = self.m_left_gp.position + self.m_left_len - 1;
+        let right_end = self.m_right_gp.position + self.m_right_len - 1;
+
+        if left_end > left_break.position + JUNCTION_OVERLAP_TOLERANCE
+            || self.m_right_gp.position < right_break.position - JUNCTION_OVERLAP_TOLERANCE
+        {
+            return None;
+        }
+
+        let insert = (left_break.position - self.m_left_gp.position + 1)
+            + (right_end - right_break.position + 1);
+
+        if insert <= 0 {
+            return None;
+        }
+
+        Some(insert)
+    }
+
+    fn key(&self) -> (i32, i32) {
+        (self.m_left_gp.position, self.m_right_gp.position)
+    }
+}
+
+/// spanning pairs at distinct positions, duplicated fragments are counted once
+pub(crate) fn unique_spanning_pairs(pairs: &[SpanningPair]) -> usize {
+    let mut keys = pairs.iter().map(|sp| sp.key()).collect::<Vec<_>>();
+    keys.sort_unstable();
+    keys.dedup();
+
+    keys.len()
+}
+
+/// start of the reverse complement of a `len` bp read starting at `gp`
+pub(crate) fn reverse_complement_gp(gp: &GenePos, len: i32) -> GenePos {
+    GenePos {
+        contig: gp.contig,
+        position: -gp.position - (len - 1),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gp(contig: i16, position: i32) -> GenePos {
+        GenePos { contig, position }
+    }
+
+    #[test]
+    fn insert_size_by_orientation_and_distance() {
+        // left mate ends 50bp before the break, right mate starts 30bp after it
+        let sp = SpanningPair {
+            m_name: "@p1".to_owned(),
+            m_left_gp: gp(0, 8000),
+            m_left_len: 100,
+            m_right_gp: gp(2, 22030),
+            m_right_len: 100,
+        };
+
+        assert_eq!(sp.insert_size(&gp(0, 8149), &gp(2, 22000)), Some(280));
+        // wrong partner gene
+        assert_eq!(sp.insert_size(&gp(0, 8149), &gp(1, 22000)), None);
+        // the left mate would lie behind the junction
+        assert_eq!(sp.insert_size(&gp(0, 8050), &gp(2, 22000)), None);
+        // reverse strand break
+        assert_eq!(sp.insert_size(&gp(0, 8149), &gp(2, -22000)), None);
+
+        let rc = reverse_complement_gp(&sp.m_left_gp, sp.m_left_len);
+        assert_eq!(rc, gp(0, -8099));
+        assert_eq!(reverse_complement_gp(&rc, sp.m_left_len), sp.m_left_gp);
+    }
+
+    #[test]
+    fn duplicated_fragments_counted_once() {
+        let sp = SpanningPair {
+            m_name: "@p1".to_owned(),
+            m_left_gp: gp(0, 8000),
+            m_left_len: 100,
+            m_right_gp: gp(2, 22030),
+            m_right_len: 100,
+        };
+        let mut other = sp.clone();
+        other.m_right_gp.position += 5;
+
+        assert_eq!(unique_spanning_pairs(&[sp.clone(), sp.clone(), other]), 2);
+    }
+}
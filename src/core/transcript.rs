@@ -0,0 +1,312 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use super::{fusion_result::FusionResult, fusion_scan::Error, gene::Gene};
+
+// the standard genetic code, codons ordered by bases T, C, A, G
+const CODON_TABLE: &[u8; 64] = b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG";
+
+/// How the coding sequences of the partners are joined, the 5' partner being the gene read first
+/// along the fusion transcript.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum FrameType {
+    InFrame,
+    OutOfFrame,
+    // a break in the 5'UTR of either partner
+    Utr5,
+    // no coding sequence of a partner, partners transcribed in conflicting directions, or the
+    // 5' partner stopping before the junction
+    #[default]
+    NonCoding,
+}
+
+impl FrameType {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            FrameType::InFrame => "in-frame",
+            FrameType::OutOfFrame => "out-of-frame",
+            FrameType::Utr5 => "5'UTR",
+            FrameType::NonCoding => "non-coding",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct ReadingFrame {
+    pub(crate) m_type: FrameType,
+    // the fusion protein from the start codon of the 5' partner to the first stop, empty without one
+    pub(crate) m_protein: String,
+    // 1-based amino acid position of a stop codon before the end of the 3' partner's coding
+    // sequence, or of any stop when out of frame
+    pub(crate) m_premature_stop: Option<usize>,
+    // coding bases of the 5' partner before the junction, all of them for a break after its stop
+    pub(crate) m_five_coding: usize,
+    // coding bases of the 3' partner left out before its break
+    pub(crate) m_three_skipped: usize,
+    // the 3' break is before the start codon of the 3' partner, whose coding sequence is all kept
+    pub(crate) m_three_intact: bool,
+}
+
+impl ReadingFrame {
+    pub(crate) fn protein_length(&self) -> usize {
+        self.m_protein.len()
+    }
+}
+
+fn complement(b: u8) -> u8 {
+    match b {
+        b'A' => b'T',
+        b'T' => b'A',
+        b'C' => b'G',
+        b'G' => b'C',
+        other => other,
+    }
+}
+
+// comparable positions along the transcription of the gene
+fn sense(gene: &Gene, pos: i32) -> i32 {
+    if gene.is_reversed() {
+        -pos
+    } else {
+        pos
+    }
+}
+
+/// Spliced sequence of the gene from chromosome position `from` to `to` in transcript order,
+/// both included, from the first or to the last exon if not given. `seq` is the forward gene
+/// sequence starting at `m_start`.
+pub(crate) fn exonic_bases(gene: &Gene, seq: &str, from: Option<i32>, to: Option<i32>) -> String {
+    let seq = seq.as_bytes();
+    let from = from.map_or(i32::MIN, |p| sense(gene, p));
+    let to = to.map_or(i32::MAX, |p| sense(gene, p));
+
+    let mut exons = gene.m_exons.iter().map(|e| (e.start.min(e.end), e.start.max(e.end))).collect::<Vec<_>>();
+    exons.sort_unstable_by_key(|&(start, _)| sense(gene, start));
+
+    let mut bases = Vec::new();
+    for (start, end) in exons.into_iter() {
+        let positions: Box<dyn Iterator<Item = i32>> = if gene.is_reversed() {
+            Box::new((start..=end).rev())
+        } else {
+            Box::new(start..=end)
+        };
+
+        for p in positions.filter(|&p| (from..=to).contains(&sense(gene, p))) {
+            let Some(&b) = seq.get((p - gene.m_start) as usize) else {
+                continue;
+            };
+            let b = b.to_ascii_uppercase();
+            bases.push(if gene.is_reversed() { complement(b) } else { b });
+        }
+    }
+
+    String::from_utf8(bases).unwrap()
+}
+
+/// amino acids of the full codons of `seq`, `*` for stops and `X` for codons with other bases
+pub(crate) fn translate(seq: &str) -> String {
+    let code = |b: u8| match b {
+        b'T' => Some(0),
+        b'C' => Some(1),
+        b'A' => Some(2),
+        b'G' => Some(3),
+        _ => None,
+    };
+
+    seq.as_bytes()
+        .chunks_exact(3)
+        .map(|c| match (code(c[0]), code(c[1]), code(c[2])) {
+            (Some(a), Some(b), Some(c)) => CODON_TABLE[a * 16 + b * 4 + c] as char,
+            _ => 'X',
+        })
+        .collect()
+}
+
+/// The spliced transcript joining the exons of `five` up to `five_break` with the exons of
+/// `three` from `three_break`, and the number of bases of `five` in it.
+pub(crate) fn fusion_transcript(
+    five: &Gene,
+    five_seq: &str,
+    five_break: i32,
+    three: &Gene,
+    three_seq: &str,
+    three_break: i32,
+) -> (String, usize) {
+    let mut seq = exonic_bases(five, five_seq, None, Some(five_break));
+    let junction = seq.len();
+    seq.push_str(&exonic_bases(three, three_seq, Some(three_break), None));
+
+    (seq, junction)
+}
+
+/// The reading frame of the transcript joining `five` up to `five_break` with `three` from
+/// `three_break`, chromosome positions of the last and the first base of each partner.
+pub(crate) fn reading_frame(
+    five: &Gene,
+    five_seq: &str,
+    five_break: i32,
+    three: &Gene,
+    three_seq: &str,
+    three_break: i32,
+) -> ReadingFrame {
+    let (Some(cds5), Some(cds3)) = (five.m_cds, three.m_cds) else {
+        return ReadingFrame::default();
+    };
+    // start and stop codons in transcript order
+    let ends = |gene: &Gene, (a, b): (i32, i32)| if gene.is_reversed() { (a.max(b), a.min(b)) } else { (a.min(b), a.max(b)) };
+    let (cds5_first, cds5_last) = ends(five, cds5);
+    let (cds3_first, cds3_last) = ends(three, cds3);
+
+    let five_end = if sense(five, five_break) > sense(five, cds5_last) { cds5_last } else { five_break };
+    let coding5 = exonic_bases(five, five_seq, Some(cds5_first), Some(five_end));
+    let tail3 = exonic_bases(three, three_seq, Some(three_break), None);
+    let skipped3 = exonic_bases(three, three_seq, Some(cds3_first), None).len().saturating_sub(tail3.len());
+    let joined = ReadingFrame {
+        m_five_coding: coding5.len(),
+        m_three_skipped: skipped3,
+        m_three_intact: sense(three, three_break) < sense(three, cds3_first),
+        ..Default::default()
+    };
+
+    if sense(five, five_break) > sense(five, cds5_last) || sense(three, three_break) > sense(three, cds3_last) {
+        return joined;
+    }
+    if sense(five, five_break) < sense(five, cds5_first) {
+        return ReadingFrame { m_type: FrameType::Utr5, ..joined };
+    }
+
+    let m_type = if joined.m_three_intact {
+        FrameType::Utr5
+    } else if (coding5.len() as i64 - skipped3 as i64).rem_euclid(3) == 0 {
+        FrameType::InFrame
+    } else {
+        FrameType::OutOfFrame
+    };
+
+    let mut protein = translate(&(coding5.clone() + &tail3));
+    let stop = protein.find('*');
+    let m_premature_stop = match (m_type, stop) {
+        (FrameType::InFrame, Some(s)) => {
+            let cds3_len = exonic_bases(three, three_seq, Some(cds3_first), Some(cds3_last)).len();
+            // the stop codon of the 3' partner, 0-based
+            let natural_stop = (coding5.len() + cds3_len - skipped3) / 3 - 1;
+            (s < natural_stop).then_some(s + 1)
+        }
+        (FrameType::OutOfFrame, Some(s)) => Some(s + 1),
+        _ => None,
+    };
+
+    protein.truncate(stop.unwrap_or(protein.len()));
+
+    ReadingFrame {
+        m_type,
+        m_protein: protein,
+        m_premature_stop,
+        ..joined
+    }
+}
+
+/// The transcript and the protein of every fusion, ids are those of the JSON report followed by
+/// the type of the record. Fusions transcribed in conflicting directions have neither.
+pub(crate) fn write_fusion_fasta(file: &str, results: &[FusionResult]) -> Result<(), Error> {
+    let mut f = BufWriter::new(File::create(file)?);
+
+    for fr in results.iter().filter(|fr| !fr.m_transcript.is_empty()) {
+        writeln!(
+            f,
+            ">{}|transcript length={} break={} {}-{}",
+            fr.id(),
+            fr.m_transcript.len(),
+            fr.m_transcript_break,
+            fr.five_prime_gene().m_name,
+            fr.three_prime_gene().m_name
+        )?;
+        writeln!(f, "{}", fr.m_transcript)?;
+
+        let frame = &fr.m_reading_frame;
+        if !frame.m_protein.is_empty() {
+            writeln!(f, ">{}|protein length={} frame={}", fr.id(), frame.protein_length(), frame.m_type.name())?;
+            writeln!(f, "{}", frame.m_protein)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gene(name: &str, start: i32, exons: &[(i32, i32)], cds: (i32, i32)) -> Gene {
+        let mut gene = Gene::new(name.to_string(), "chr1".to_string(), start, start + 1000);
+        for (i, &(s, e)) in exons.iter().enumerate() {
+            gene.add_exon(i as i32 + 1, s, e);
+        }
+        gene.m_cds = Some(cds);
+        gene
+    }
+
+    #[test]
+    fn frame_of_fusions() {
+        assert_eq!(translate("ATGGCCTAAGG"), "MA*");
+
+        // 5' partner: exon 1 at 0~9 is ccATGAAACC, exon 2 at 20~29 is CTAAggcccc, coding 2~23
+        let five_seq = "CCATGAAACCttttttttttCTAAGGCCCC";
+        let five = gene("A", 0, &[(0, 9), (20, 29)], (2, 23));
+        assert_eq!(exonic_bases(&five, five_seq, Some(2), Some(23)), "ATGAAACCCTAA");
+        assert_eq!(exonic_bases(&five, five_seq, Some(2), Some(15)), "ATGAAACC");
+
+        // 3' partner: exon 1 at 100~109 is ATGCCCGGGA, exon 2 at 120~129 is AATTTTAGcc, coding 100~127
+        let three_seq = "ATGCCCGGGAttttttttttAATTTTAGCC";
+        let three = gene("B", 100, &[(100, 109), (120, 129)], (100, 127));
+
+        // intron to intron, ATGAAACC joined to exon 2 of B skipping 10 coding bases, ATG AAA CCA ATT TTA GCC
+        let frame = reading_frame(&five, five_seq, 15, &three, three_seq, 115);
+        assert_eq!(frame.m_type, FrameType::OutOfFrame);
+        assert_eq!(frame.m_protein, "MKPILA");
+        assert_eq!(frame.m_premature_stop, None);
+
+        // ATGAAA joined to CCCGGGA AATTTTAG, ATG AAA CCC GGG AAA TTT TAG, ending at the stop of B
+        let frame = reading_frame(&five, five_seq, 7, &three, three_seq, 103);
+        assert_eq!(frame.m_type, FrameType::InFrame);
+        assert_eq!(frame.m_protein, "MKPGKF");
+        assert_eq!(frame.m_premature_stop, None);
+
+        // ATGAAACCCT joined to CCGGGA AATTTTAG, ATG AAA CCC TCC GGG AAA TTT TAG
+        let frame = reading_frame(&five, five_seq, 21, &three, three_seq, 104);
+        assert_eq!(frame.m_type, FrameType::InFrame);
+        assert_eq!(frame.m_protein, "MKPSGKF");
+        assert_eq!(frame.m_premature_stop, None);
+
+        // the stop TAA of A is kept and comes first
+        let frame = reading_frame(&five, five_seq, 23, &three, three_seq, 103);
+        assert_eq!(frame.m_type, FrameType::InFrame);
+        assert_eq!(frame.m_protein, "MKP");
+        assert_eq!(frame.m_premature_stop, Some(4));
+
+        // the whole transcripts, exon 1 of A with exon 2 of B
+        let (seq, junction) = fusion_transcript(&five, five_seq, 15, &three, three_seq, 115);
+        assert_eq!(seq, "CCATGAAACCAATTTTAGCC");
+        assert_eq!(junction, 10);
+
+        // breaks in the 5'UTR, after the stop of the 5' partner, or without coding sequence
+        assert_eq!(reading_frame(&five, five_seq, 1, &three, three_seq, 103).m_type, FrameType::Utr5);
+        assert_eq!(reading_frame(&five, five_seq, 25, &three, three_seq, 103).m_type, FrameType::NonCoding);
+        let mut noncoding = three.clone();
+        noncoding.m_cds = None;
+        assert_eq!(reading_frame(&five, five_seq, 7, &noncoding, three_seq, 103).m_type, FrameType::NonCoding);
+
+        // a reverse gene is read from its end, exons listed from the end of the chromosome
+        let rc = |s: &str| String::from_utf8(s.bytes().rev().map(complement).collect()).unwrap();
+        let mut five_rc = Gene::new("C".to_string(), "chr1".to_string(), 0, 1000);
+        five_rc.add_exon(1, 20, 29);
+        five_rc.add_exon(2, 0, 9);
+        five_rc.m_cds = Some((6, 27));
+        let five_rc_seq = rc(five_seq);
+        assert!(five_rc.is_reversed());
+        assert_eq!(exonic_bases(&five_rc, &five_rc_seq, Some(27), Some(6)), "ATGAAACCCTAA");
+        assert_eq!(reading_frame(&five_rc, &five_rc_seq, 22, &three, three_seq, 103).m_type, FrameType::InFrame);
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use crate::aux::global_settings::global_settings;
+
+use super::{fusion_result::FusionResult, fusion_scan::Error};
+
+// an empty field
+fn or_dot(s: &str) -> &str {
+    if s.is_empty() {
+        "."
+    } else {
+        s
+    }
+}
+
+/// one line per reported fusion, positions signed by strand as in the JSON report
+pub(crate) fn write_fusions_tsv(file: &str, results: &[FusionResult]) -> Result<(), Error> {
+    let mut f = BufWriter::new(File::create(file)?);
+
+    writeln!(
+        f,
+        "id\tleft_gene\tleft_chr\tleft_position\tright_gene\tright_chr\tright_position\tevent_type\tunique\ttotal\tscore\tvaf\tdonor_motif\tacceptor_motif\tsplice_motif\tmicrohomology_length\tmicrohomology\tleft_break_first\tleft_break_last\tright_break_first\tright_break_last\treading_frame\tprotein_length\tpremature_stop\tknown\tknown_sources\tread_through_gap"
+    )?;
+
+    let known_fusions = !global_settings().known_fusions_file.is_empty();
+    for fr in results.iter() {
+        let bp = fr.breakpoints();
+        let bc = &fr.m_break_context;
+        let ((left_first, left_last), (right_first, right_last)) = fr.break_ranges();
+
+        writeln!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:.4}\t{:.4}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            fr.id(),
+            fr.m_left_gene.m_name,
+            bp.left_chr,
+            bp.left_pos,
+            fr.m_right_gene.m_name,
+            bp.right_chr,
+            bp.right_pos,
+            fr.m_event_type.name(),
+            fr.m_unique,
+            fr.m_matches.len(),
+            fr.m_score,
+            fr.m_allele_fraction.m_vaf,
+            or_dot(&bc.m_donor),
+            or_dot(&bc.m_acceptor),
+            bc.splice_class(),
+            bc.microhomology_len(),
+            or_dot(&bc.m_microhomology),
+            left_first,
+            left_last,
+            right_first,
+            right_last,
+            fr.m_reading_frame.m_type.name(),
+            fr.m_reading_frame.protein_length(),
+            fr.m_reading_frame.m_premature_stop.map_or(".".to_string(), |s| s.to_string()),
+            if known_fusions { fr.m_known.m_status.name() } else { "." },
+            or_dot(&fr.m_known.m_sources.join(",")),
+            fr.m_read_through.map_or(".".to_string(), |gap| gap.to_string())
+        )?;
+    }
+
+    Ok(())
+}
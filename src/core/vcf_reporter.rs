@@ -0,0 +1,179 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use chrono::Local;
+
+use crate::aux::global_settings::global_settings;
+
+use super::{fasta_reader::FastaReader, fusion_result::FusionResult, fusion_scan::Error, html_reporter::FUSIONSCAN_VER};
+
+const INFO_HEADER: &[&str] = &[
+    "##INFO=<ID=SVTYPE,Number=1,Type=String,Description=\"Type of structural variant\">",
+    "##INFO=<ID=MATEID,Number=1,Type=String,Description=\"ID of the mate breakend\">",
+    "##INFO=<ID=EVENT,Number=1,Type=String,Description=\"ID of the event, shared by the reciprocal fusions of a balanced translocation\">",
+    "##INFO=<ID=GENE,Number=1,Type=String,Description=\"Gene of this breakend\">",
+    "##INFO=<ID=MATE_GENE,Number=1,Type=String,Description=\"Gene of the mate breakend\">",
+    "##INFO=<ID=REGION,Number=1,Type=String,Description=\"Whether the break is in an exon or an intron\">",
+    "##INFO=<ID=REGION_ID,Number=1,Type=Integer,Description=\"Number of the exon or intron of the break\">",
+    "##INFO=<ID=UNIQUE,Number=1,Type=Integer,Description=\"Unique reads supporting the fusion\">",
+    "##INFO=<ID=TOTAL,Number=1,Type=Integer,Description=\"All reads supporting the fusion\">",
+    "##INFO=<ID=EVENT_TYPE,Number=1,Type=String,Description=\"fusion, deletion, tandem_duplication or inversion\">",
+];
+
+const FILTER_HEADER: &[&str] = &[
+    "##FILTER=<ID=PON,Description=\"Found in the panel of normals\">",
+    "##FILTER=<ID=READ_THROUGH,Description=\"Read-through candidate of neighbouring genes\">",
+];
+
+/// ALT of a breakend with reference base `t` joined to the mate at `mate_chr:mate_pos`, 1-based.
+///
+/// `joined_after` if the mate sequence follows `t`, `mate_rightward` if it goes on to the right of
+/// the mate position, as in `t[p[`, `t]p]`, `[p[t` and `]p]t`.
+pub(crate) fn breakend_alt(t: char, mate_chr: &str, mate_pos: i32, joined_after: bool, mate_rightward: bool) -> String {
+    let bracket = if mate_rightward { '[' } else { ']' };
+    let p = format!("{}{}:{}{}", bracket, mate_chr, mate_pos, bracket);
+
+    if joined_after {
+        format!("{}{}", t, p)
+    } else {
+        format!("{}{}", p, t)
+    }
+}
+
+// 0-based position on the reference
+fn ref_base(reference: &FastaReader, chr: &str, pos: i32) -> char {
+    reference
+        .m_all_contigs
+        .get(chr)
+        .and_then(|seq| seq.as_bytes().get(pos as usize))
+        .map_or('N', |b| b.to_ascii_uppercase() as char)
+}
+
+// FILTER marks of a fusion, PASS without any
+fn filter(fr: &FusionResult) -> String {
+    let mut marks = Vec::new();
+    if fr.m_pon.is_some() {
+        marks.push("PON");
+    }
+    if fr.m_read_through.is_some() {
+        marks.push("READ_THROUGH");
+    }
+
+    if marks.is_empty() {
+        "PASS".to_string()
+    } else {
+        marks.join(";")
+    }
+}
+
+/// VCF 4.3 with a pair of mate breakends per fusion and the contigs of the reference.
+///
+/// The left side of a fusion ends at its break and the right side starts at its break, along
+/// the strands of the reads, so a forward left side is followed by its mate and a reverse one is
+/// preceded by it. Breakends are named after `FusionResult::id` and grouped into the events of
+/// the reports, so both fusions of a reciprocal translocation share their EVENT.
+pub(crate) fn write_vcf(file: &str, results: &[FusionResult], reference: &FastaReader) -> Result<(), Error> {
+    let mut f = BufWriter::new(File::create(file)?);
+
+    writeln!(f, "##fileformat=VCFv4.3")?;
+    writeln!(f, "##fileDate={}", Local::now().format("%Y%m%d"))?;
+    writeln!(f, "##source=GeneFuse {}", FUSIONSCAN_VER)?;
+    writeln!(f, "##reference={}", reference.m_fasta_file)?;
+    for (name, seq) in reference.m_all_contigs.iter() {
+        writeln!(f, "##contig=<ID={},length={}>", name, seq.len())?;
+    }
+    for line in INFO_HEADER.iter().chain(FILTER_HEADER.iter()) {
+        writeln!(f, "{}", line)?;
+    }
+    writeln!(f, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO")?;
+
+    let mut records = Vec::new();
+    for fr in results.iter() {
+        let filter = filter(fr);
+        if global_settings().marked_only_for_vcf && filter != "PASS" {
+            continue;
+        }
+
+        let bp = fr.breakpoints();
+        let id = fr.id();
+        let (left_forward, right_forward) = (fr.m_left_gp.position >= 0, fr.m_right_gp.position >= 0);
+        let (left_pos, right_pos) = (bp.left_pos.abs(), bp.right_pos.abs());
+
+        let sides = [
+            (
+                &bp.left_chr,
+                left_pos,
+                &fr.m_left_gene.m_name,
+                &fr.m_right_gene.m_name,
+                fr.m_left_is_exon,
+                fr.m_left_exon_or_intron_id,
+                breakend_alt(ref_base(reference, &bp.left_chr, left_pos), &bp.right_chr, right_pos + 1, left_forward, right_forward),
+            ),
+            (
+                &bp.right_chr,
+                right_pos,
+                &fr.m_right_gene.m_name,
+                &fr.m_left_gene.m_name,
+                fr.m_right_is_exon,
+                fr.m_right_exon_or_intron_id,
+                breakend_alt(ref_base(reference, &bp.right_chr, right_pos), &bp.left_chr, left_pos + 1, !right_forward, !left_forward),
+            ),
+        ];
+
+        for (s, (chr, pos, gene, mate_gene, is_exon, region_id, alt)) in sides.into_iter().enumerate() {
+            let info = format!(
+                "SVTYPE=BND;MATEID={}_{};EVENT=E{};GENE={};MATE_GENE={};REGION={};REGION_ID={};UNIQUE={};TOTAL={};EVENT_TYPE={}",
+                id,
+                2 - s,
+                fr.m_event_id,
+                gene,
+                mate_gene,
+                if is_exon { "exon" } else { "intron" },
+                region_id,
+                fr.m_unique,
+                fr.m_matches.len(),
+                fr.m_event_type.name()
+            );
+            records.push((
+                chr.clone(),
+                pos + 1,
+                format!(
+                    "{}\t{}\t{}_{}\t{}\t{}\t.\t{}\t{}",
+                    chr,
+                    pos + 1,
+                    id,
+                    s + 1,
+                    ref_base(reference, chr, pos),
+                    alt,
+                    filter,
+                    info
+                ),
+            ));
+        }
+    }
+
+    // sorted like the contigs of the header, by name
+    records.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+    for (_, _, line) in records.iter() {
+        writeln!(f, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bracket_notation() {
+        // a forward left side followed by a forward right side, and the mate preceded by it
+        assert_eq!(breakend_alt('G', "chr2", 321682, true, true), "G[chr2:321682[");
+        assert_eq!(breakend_alt('T', "chr13", 123456, false, false), "]chr13:123456]T");
+        // an inversion joins both sides after or both before
+        assert_eq!(breakend_alt('A', "chr2", 421681, true, false), "A]chr2:421681]");
+        assert_eq!(breakend_alt('C', "chr17", 198983, false, true), "[chr17:198983[C");
+    }
+}
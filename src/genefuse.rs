@@ -3,9 +3,9 @@ use std::{
 };
 
 use crate::{
-    argparse::RunConfig,
+    argparse::{BuildPonConfig, RunConfig},
     aux::global_settings::{global_settings, global_settings_w},
-    core::{fusion_scan::FusionScan, html_reporter::FUSIONSCAN_VER},
+    core::{fusion_scan::FusionScan, html_reporter::FUSIONSCAN_VER, pon::PanelOfNormals},
     utils::{check_file_valid, logging::init_logger},
 };
 
@@ -34,20 +34,93 @@ pub(crate) fn genefuse(config: RunConfig) {
 
     
     log::debug!(">> Scanning Fusion...");
-    fs.scan().unwrap();
+    if let Err(e) = fs.scan() {
+        eprintln!("{}", e);
+        exit(-1);
+    }
 
     println!("# genefuse v{}, time used: {} seconds\n", FUSIONSCAN_VER, timer.elapsed().as_secs_f32());
 
     log::info!("done");
 }
 
+pub(crate) fn build_pon(config: BuildPonConfig) {
+    init_logger();
+
+    for file in config.json_files.iter() {
+        check_file_valid(file);
+    }
+
+    let pon = match PanelOfNormals::from_json_files(&config.json_files, config.tolerance) {
+        Ok(pon) => pon,
+        Err(e) => {
+            eprintln!("Failed to read the JSON reports: {}", e);
+            exit(-1);
+        }
+    };
+
+    if let Err(e) = pon.write(&config.output, config.min_samples) {
+        eprintln!("Failed to write the panel of normals {}: {}", config.output, e);
+        exit(-1);
+    }
+
+    println!(
+        "# {} junctions of {} samples, {} found in at least {} samples are stored in {}",
+        pon.m_junctions.len(),
+        pon.m_total_samples,
+        pon.m_junctions.iter().filter(|j| j.m_samples >= config.min_samples).count(),
+        config.min_samples,
+        config.output
+    );
+}
+
 fn prepare_run(config: &RunConfig) {
     {
         let mut global_settings = global_settings_w();
         global_settings.set_unique_requirement(config.unique);
         global_settings.set_deletion_threshold(config.deletion);
+        global_settings.set_duplication_threshold(config.duplication);
+        global_settings.set_inversion_threshold(config.inversion);
         global_settings.set_output_deletions(config.output_deletion);
         global_settings.set_output_untranslated(config.output_untranslated);
+        global_settings.set_index_stats_file(config.index_stats.clone());
+        global_settings.set_paralog_unique_ratio(config.paralog_unique_ratio);
+        global_settings.set_exclude_bed_file(config.exclude_bed.clone());
+        global_settings.set_exclude_contigs(config.exclude_contigs.clone());
+        global_settings.set_rejected_reads_file(config.rejected_reads.clone());
+        global_settings.set_consensus_fasta_file(config.consensus_fasta.clone());
+        global_settings.set_tsv_file(config.tsv.clone());
+        global_settings.set_fusion_fasta_file(config.fusion_fasta.clone());
+        global_settings.set_vcf_file(config.vcf.clone());
+        global_settings.set_marked_only_for_vcf(config.vcf_pass_only);
+        global_settings.set_spanning_max_insert(config.spanning_max_insert);
+        global_settings.set_count_spanning_pairs(config.count_spanning_pairs);
+        global_settings.set_reciprocal_max_distance(config.reciprocal_max_distance);
+        global_settings.set_collapse_reciprocal(config.collapse_reciprocal);
+        global_settings.set_min_score(config.min_score);
+        global_settings.set_sort_by_score(config.sort_by == "score");
+        global_settings.set_pon_file(config.pon.clone());
+        global_settings.set_pon_tolerance(config.pon_tolerance);
+        global_settings.set_pon_remove(config.pon_remove);
+        global_settings.set_hotspot_file(config.hotspots.clone());
+        global_settings.set_domain_file(config.domains.clone());
+        global_settings.set_known_fusions_file(config.known_fusions.clone());
+        global_settings.set_known_only(config.known_only);
+        global_settings.set_readthrough_distance(config.readthrough_distance);
+        global_settings.set_readthrough_demote(config.readthrough_demote);
+        global_settings.set_readthrough_remove(config.readthrough_remove);
+        // back-splice junctions are found at exon boundaries, where RNA mode moves the breaks
+        global_settings.set_rna_mode(config.rna || config.circrna);
+        global_settings.set_circrna_mode(config.circrna);
+        global_settings.set_skip_key_dup_threshold(config.skip_key_dup_threshold);
+        global_settings.set_major_gene_key_requirement(config.major_key_requirement);
+        global_settings.set_minor_gene_key_requirement(config.minor_key_requirement);
+        global_settings.set_mismatch_threshold(config.mismatch_threshold);
+        global_settings.set_diff_threshold(config.diff_threshold);
+        global_settings.set_low_complexity_length(config.low_complexity_length);
+        global_settings.set_low_complexity_discontinuity(config.low_complexity_discontinuity);
+        global_settings.set_min_ref_length(config.min_ref_length);
+        global_settings.set_ref_flank_discontinuity(config.ref_flank_discontinuity);
     }
 
     log::debug!("global_settings set.");
@@ -72,6 +145,14 @@ fn prepare_run(config: &RunConfig) {
 
     COMMAND.set(command).unwrap();
 
+    if config.minor_key_requirement > config.major_key_requirement {
+        eprintln!(
+            "--minor_key_requirement ({}) should not be larger than --major_key_requirement ({}).",
+            config.minor_key_requirement, config.major_key_requirement
+        );
+        exit(-1);
+    }
+
     check_file_valid(&config.ref_file);
     check_file_valid(&config.r1_file);
 
@@ -83,5 +164,25 @@ fn prepare_run(config: &RunConfig) {
         check_file_valid(&config.fusion_file);
     }
 
+    if config.exclude_bed != "" {
+        check_file_valid(&config.exclude_bed);
+    }
+
+    if config.pon != "" {
+        check_file_valid(&config.pon);
+    }
+
+    if config.hotspots != "" {
+        check_file_valid(&config.hotspots);
+    }
+
+    if config.domains != "" {
+        check_file_valid(&config.domains);
+    }
+
+    if config.known_fusions != "" {
+        check_file_valid(&config.known_fusions);
+    }
+
     println!("\n# {}\n", COMMAND.get().unwrap());
 }
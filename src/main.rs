@@ -1,6 +1,6 @@
-use argparse::set_configs;
+use argparse::{set_configs, Config};
 // use genefuse::genefuse;
-use genefuse::genefuse;
+use genefuse::{build_pon, genefuse};
 
 mod argparse;
 mod genefuse;
@@ -13,7 +13,8 @@ mod core;
 static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 fn main() {
-    let config = set_configs();
-    
-    genefuse(config)
+    match set_configs() {
+        Config::Run(config) => genefuse(*config),
+        Config::BuildPon(config) => build_pon(config),
+    }
 }